@@ -0,0 +1,85 @@
+//! time the hot counting paths on groceries.csv
+//!
+//! compare `cargo bench` with `cargo bench --features fxhash` to see what the hasher buys
+//! and with `--features parallel` to see what counting on all cores buys
+//!
+//! the sparse cases run on generated transactions, with and without the bloom prefilter
+
+use std::{hint::black_box, time::Instant};
+
+use apriori::{item_counts, minimal_generators, AprioriConfig, Txn};
+
+/// read groceries.csv into transactions
+fn load_groceries() -> Vec<Txn> {
+    let filename = concat!(env!("CARGO_MANIFEST_DIR"), "/groceries.csv");
+    let mut reader = csv::Reader::from_path(filename).expect("failed to read the csv file");
+
+    reader.records().enumerate().map(|(i, record)| {
+        let record = record.expect("failed to get items from the record");
+        Txn::new(i, record.iter().filter(|x| !x.is_empty()).map(|x| x.to_string()).collect())
+    }).collect()
+}
+
+/// generate num transactions of 2 to 6 items each, drawn from 2000 distinct items
+///
+/// only the first 40 items are frequent, and their pairs appear in very few transactions
+fn sparse_txn_set(num: usize) -> Vec<Txn> {
+    // xorshift, a fixed seed keeps the data the same between runs
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |bound: u64| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state % bound
+    };
+
+    (0..num).map(|i| {
+        let len = 2 + next(5);
+        let mut items: Vec<String> = (0..len).map(|_| {
+            // a quarter of the draws come from the first 40 items
+            let item = if next(4) == 0 { next(40) } else { next(2000) };
+            format!("item{}", item)
+        }).collect();
+        items.sort();
+        items.dedup();
+        Txn::new(i, items)
+    }).collect()
+}
+
+/// run f iterations times and report the mean time of one iteration
+fn time<F: FnMut()>(name: &str, iterations: u32, mut f: F) {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        f();
+    }
+    println!("{}: {:?} per iteration", name, start.elapsed() / iterations);
+}
+
+fn main() {
+    let txn_set = load_groceries();
+
+    time("item_counts", 100, || {
+        black_box(item_counts(&txn_set));
+    });
+
+    let result = AprioriConfig::new().min_support(0.005).run_on_txn_set(load_groceries()).expect("failed to mine groceries.csv");
+
+    time("minimal_generators", 20, || {
+        black_box(minimal_generators(&result.fre_sets));
+    });
+
+    let exact = AprioriConfig::new().min_support(0.005).min_confidence(0.9);
+    let prefiltered = exact.clone().bloom_prefilter(true);
+
+    let exact_result = exact.run_on_txn_set(sparse_txn_set(50_000)).expect("failed to mine the sparse transactions");
+    let prefiltered_result = prefiltered.run_on_txn_set(sparse_txn_set(50_000)).expect("failed to mine the sparse transactions");
+    assert_eq!(format!("{:?}", exact_result.fre_sets), format!("{:?}", prefiltered_result.fre_sets));
+
+    time("sparse exact", 3, || {
+        black_box(exact.run_on_txn_set(sparse_txn_set(50_000)).expect("failed to mine the sparse transactions"));
+    });
+
+    time("sparse bloom_prefilter", 3, || {
+        black_box(prefiltered.run_on_txn_set(sparse_txn_set(50_000)).expect("failed to mine the sparse transactions"));
+    });
+}
@@ -0,0 +1,143 @@
+//! the compact binary format of the rules, behind the binary feature
+
+use std::collections::HashMap;
+
+use crate::AssociationRule;
+
+/// ## write the rules to filename in a compact binary format
+///
+/// every distinct item is stored once in a dictionary and the rules refer to it by id,
+/// read it back with read_rules_bin
+///
+/// layout, all integers little endian:
+/// "APRB", version u8, item count u32, (len u32, utf-8 bytes) per item,
+/// rule count u32, (from len u32, ids u32.., to len u32, ids u32.., sup f64, conf f64, lift f64, conviction f64, leverage f64,
+/// antecedent_count u64, consequent_count u64, union_count u64) per rule
+pub fn write_rules_bin(filename: &str, association_rules_set: &[AssociationRule]) -> std::io::Result<()> {
+
+    // intern the items in first-seen order
+    let mut ids: HashMap<&str, u32> = HashMap::new();
+    let mut dictionary: Vec<&str> = Vec::new();
+    for rule in association_rules_set.iter() {
+        for item in rule.from.iter().chain(rule.to.iter()) {
+            ids.entry(item).or_insert_with(|| {
+                dictionary.push(item);
+                (dictionary.len() - 1) as u32
+            });
+        }
+    }
+
+    let mut buf: Vec<u8> = Vec::new();
+    buf.extend_from_slice(BIN_MAGIC);
+    buf.push(BIN_VERSION);
+
+    buf.extend_from_slice(&(dictionary.len() as u32).to_le_bytes());
+    for item in dictionary.iter() {
+        buf.extend_from_slice(&(item.len() as u32).to_le_bytes());
+        buf.extend_from_slice(item.as_bytes());
+    }
+
+    buf.extend_from_slice(&(association_rules_set.len() as u32).to_le_bytes());
+    for rule in association_rules_set.iter() {
+        for side in [&rule.from, &rule.to] {
+            buf.extend_from_slice(&(side.len() as u32).to_le_bytes());
+            for item in side.iter() {
+                buf.extend_from_slice(&ids[item.as_str()].to_le_bytes());
+            }
+        }
+        buf.extend_from_slice(&rule.sup.to_le_bytes());
+        buf.extend_from_slice(&rule.conf.to_le_bytes());
+        buf.extend_from_slice(&rule.lift.to_le_bytes());
+        buf.extend_from_slice(&rule.conviction.to_le_bytes());
+        buf.extend_from_slice(&rule.leverage.to_le_bytes());
+        buf.extend_from_slice(&(rule.antecedent_count as u64).to_le_bytes());
+        buf.extend_from_slice(&(rule.consequent_count as u64).to_le_bytes());
+        buf.extend_from_slice(&(rule.union_count as u64).to_le_bytes());
+    }
+
+    std::fs::write(filename, buf)
+}
+
+/// ## read rules written by write_rules_bin
+///
+/// fails with InvalidData if the file is not in that format or is truncated
+pub fn read_rules_bin(filename: &str) -> std::io::Result<Vec<AssociationRule>> {
+
+    let buf = std::fs::read(filename)?;
+    let mut reader = BinReader { buf: &buf, pos: 0 };
+
+    if reader.take(BIN_MAGIC.len())? != BIN_MAGIC || reader.take(1)?[0] != BIN_VERSION {
+        return Err(bin_error("not a binary rules file"));
+    }
+
+    let item_num = reader.u32()? as usize;
+    let mut dictionary: Vec<String> = Vec::with_capacity(item_num.min(buf.len()));
+    for _ in 0..item_num {
+        let len = reader.u32()? as usize;
+        let item = std::str::from_utf8(reader.take(len)?).map_err(|_| bin_error("item is not valid utf-8"))?;
+        dictionary.push(item.to_string());
+    }
+
+    let rule_num = reader.u32()? as usize;
+    let mut rules: Vec<AssociationRule> = Vec::with_capacity(rule_num.min(buf.len()));
+    for _ in 0..rule_num {
+        let from = reader.items(&dictionary)?;
+        let to = reader.items(&dictionary)?;
+        let sup = reader.f64()?;
+        let conf = reader.f64()?;
+        let lift = reader.f64()?;
+        let conviction = reader.f64()?;
+        let leverage = reader.f64()?;
+        let antecedent_count = reader.u64()? as usize;
+        let consequent_count = reader.u64()? as usize;
+        let union_count = reader.u64()? as usize;
+        rules.push(AssociationRule { from, to, sup, conf, lift, conviction, leverage, antecedent_count, consequent_count, union_count });
+    }
+
+    Ok(rules)
+}
+
+const BIN_MAGIC: &[u8] = b"APRB";
+const BIN_VERSION: u8 = 4;
+
+fn bin_error(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+/// a cursor over the bytes of a binary rules file
+struct BinReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> BinReader<'a> {
+
+    fn take(&mut self, len: usize) -> std::io::Result<&'a [u8]> {
+        if self.buf.len() - self.pos < len {
+            return Err(bin_error("binary rules file is truncated"));
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> std::io::Result<u32> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> std::io::Result<u64> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn f64(&mut self) -> std::io::Result<f64> {
+        Ok(f64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn items(&mut self, dictionary: &[String]) -> std::io::Result<Vec<String>> {
+        let len = self.u32()? as usize;
+        (0..len).map(|_| {
+            let id = self.u32()? as usize;
+            dictionary.get(id).cloned().ok_or_else(|| bin_error("item id out of range"))
+        }).collect()
+    }
+}
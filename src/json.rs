@@ -0,0 +1,284 @@
+//! the json input of the crate, a reader of just enough json for serde to deserialize from,
+//! serde_json is not a dependency
+
+use crate::{AprioriError, Txn};
+
+/// ## read a json file of one array of items per transaction, e.g. `[["milk", "bread"], ["beer", "chips"]]`
+///
+/// the items are trimmed and empty ones dropped as in a csv file, the items of each Txn are sorted and its id
+/// is the position of its array, an empty array is an empty Txn, which counts as a transaction but holds no item
+///
+/// fails with AprioriError::Json if the file is not an array of arrays of strings
+pub fn create_txn_set_from_json(filename: &str) -> Result<Vec<Txn>, AprioriError> {
+
+    let content = std::fs::read_to_string(filename)?;
+    let mut reader = JsonReader { buf: content.as_bytes(), pos: 0 };
+
+    let transactions: Result<Vec<Vec<String>>, JsonError> = serde::Deserialize::deserialize(&mut reader);
+    let transactions = match transactions.and_then(|x| reader.end().map(|_| x)) {
+        Ok(transactions) => transactions,
+        Err(JsonError(message)) => return Err(AprioriError::Json { message, offset: reader.pos }),
+    };
+
+    Ok(transactions.into_iter().enumerate().map(|(i, items)| {
+        Txn::new(i, items.iter().map(|x| x.trim()).filter(|x| !x.is_empty()).map(|x| x.to_string()).collect())
+    }).collect())
+}
+
+/// the message of a json file which can't be read, AprioriError::Json adds where
+#[derive(Debug)]
+pub(crate) struct JsonError(pub(crate) String);
+
+impl std::fmt::Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for JsonError {}
+
+impl serde::de::Error for JsonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        JsonError(msg.to_string())
+    }
+}
+
+/// ## a cursor over the bytes of a json file, deserializing any value serde asks for
+///
+/// null, booleans, numbers, strings, arrays and objects, the json types map to the serde types as serde_json does
+pub(crate) struct JsonReader<'a> {
+    pub(crate) buf: &'a [u8],
+    pub(crate) pos: usize,
+}
+
+impl JsonReader<'_> {
+
+    /// the next byte which is not whitespace, without taking it
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.buf.get(self.pos) {
+            self.pos += 1;
+        }
+        self.buf.get(self.pos).copied()
+    }
+
+    /// take the next byte which is not whitespace, it must be byte
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(next) if next == byte => {},
+            Some(_) => return Err(JsonError(format!("expected '{}'", byte as char))),
+            None => return Err(JsonError("unexpected end of file".to_string())),
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// take word, the rest of null, true or false
+    fn keyword(&mut self, word: &str) -> Result<(), JsonError> {
+        if !self.buf[self.pos..].starts_with(word.as_bytes()) {
+            return Err(JsonError(format!("expected {}", word)));
+        }
+        self.pos += word.len();
+        Ok(())
+    }
+
+    /// nothing but whitespace may follow the value
+    pub(crate) fn end(&mut self) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(_) => Err(JsonError("trailing characters after the value".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// take a string from its opening quote to its closing one, unescaped
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut bytes: Vec<u8> = Vec::new();
+
+        loop {
+            let Some(&byte) = self.buf.get(self.pos) else {
+                return Err(JsonError("unterminated string".to_string()));
+            };
+            self.pos += 1;
+
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let Some(&escape) = self.buf.get(self.pos) else {
+                        return Err(JsonError("unterminated string".to_string()));
+                    };
+                    self.pos += 1;
+
+                    let unescaped = match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.unicode_escape()?,
+                        _ => return Err(JsonError(format!("invalid escape '\\{}'", escape as char))),
+                    };
+                    bytes.extend_from_slice(unescaped.encode_utf8(&mut [0; 4]).as_bytes());
+                },
+                0..=0x1f => return Err(JsonError("control character in string".to_string())),
+                _ => bytes.push(byte),
+            }
+        }
+
+        // the file is a str and a string is only split at quotes and backslashes, so the bytes are still utf-8
+        Ok(String::from_utf8(bytes).expect("a json string of a str is utf-8"))
+    }
+
+    /// the char of a \uXXXX escape, whose \u is already taken, with the second half of a surrogate pair
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| JsonError("unpaired surrogate in \\u escape".to_string()));
+        }
+
+        if !self.buf[self.pos..].starts_with(b"\\u") {
+            return Err(JsonError("unpaired surrogate in \\u escape".to_string()));
+        }
+        self.pos += 2;
+
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(JsonError("unpaired surrogate in \\u escape".to_string()));
+        }
+
+        Ok(char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)).expect("a surrogate pair is a char"))
+    }
+
+    /// take 4 hex digits
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self.buf.get(self.pos..self.pos + 4)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .and_then(|x| u32::from_str_radix(x, 16).ok())
+            .ok_or_else(|| JsonError("expected 4 hex digits in \\u escape".to_string()))?;
+        self.pos += 4;
+        Ok(digits)
+    }
+
+    /// take a number, an integer if it has no fraction or exponent
+    fn number<'de, V: serde::de::Visitor<'de>>(&mut self, visitor: V) -> Result<V::Value, JsonError> {
+        let start = self.pos;
+        while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.buf.get(self.pos) {
+            self.pos += 1;
+        }
+        let text = std::str::from_utf8(&self.buf[start..self.pos]).expect("ascii digits are utf-8");
+        let invalid = || JsonError(format!("invalid number {}", text));
+
+        if text.contains(['.', 'e', 'E']) {
+            visitor.visit_f64(text.parse().map_err(|_| invalid())?)
+        } else if text.starts_with('-') {
+            visitor.visit_i64(text.parse().map_err(|_| invalid())?)
+        } else {
+            visitor.visit_u64(text.parse().map_err(|_| invalid())?)
+        }
+    }
+}
+
+impl<'de> serde::Deserializer<'de> for &mut JsonReader<'_> {
+    type Error = JsonError;
+
+    fn deserialize_any<V: serde::de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        match self.peek() {
+            Some(b'n') => {
+                self.keyword("null")?;
+                visitor.visit_unit()
+            },
+            Some(b't') => {
+                self.keyword("true")?;
+                visitor.visit_bool(true)
+            },
+            Some(b'f') => {
+                self.keyword("false")?;
+                visitor.visit_bool(false)
+            },
+            Some(b'"') => visitor.visit_string(self.string()?),
+            Some(b'[') => {
+                self.pos += 1;
+                let value = visitor.visit_seq(JsonElements { reader: &mut *self, first: true })?;
+                self.expect(b']')?;
+                Ok(value)
+            },
+            Some(b'{') => {
+                self.pos += 1;
+                let value = visitor.visit_map(JsonElements { reader: &mut *self, first: true })?;
+                self.expect(b'}')?;
+                Ok(value)
+            },
+            Some(b'-' | b'0'..=b'9') => self.number(visitor),
+            Some(_) => Err(JsonError("expected a value".to_string())),
+            None => Err(JsonError("unexpected end of file".to_string())),
+        }
+    }
+
+    fn deserialize_option<V: serde::de::Visitor<'de>>(self, visitor: V) -> Result<V::Value, JsonError> {
+        if self.peek() == Some(b'n') {
+            self.keyword("null")?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf unit unit_struct
+        newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// the elements of a json array or the entries of a json object, whose opening bracket is taken,
+/// the closing one is left to the JsonReader
+struct JsonElements<'r, 'a> {
+    reader: &'r mut JsonReader<'a>,
+    first: bool,
+}
+
+impl JsonElements<'_, '_> {
+
+    /// whether another element follows, taking the comma before it
+    fn has_next(&mut self, close: u8) -> Result<bool, JsonError> {
+        if self.reader.peek() == Some(close) {
+            return Ok(false);
+        }
+        if !self.first {
+            self.reader.expect(b',')?;
+        }
+        self.first = false;
+        Ok(true)
+    }
+}
+
+impl<'de> serde::de::SeqAccess<'de> for JsonElements<'_, '_> {
+    type Error = JsonError;
+
+    fn next_element_seed<T: serde::de::DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, JsonError> {
+        if !self.has_next(b']')? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+}
+
+impl<'de> serde::de::MapAccess<'de> for JsonElements<'_, '_> {
+    type Error = JsonError;
+
+    fn next_key_seed<K: serde::de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, JsonError> {
+        if !self.has_next(b'}')? {
+            return Ok(None);
+        }
+        if self.reader.peek() != Some(b'"') {
+            return Err(JsonError("expected a string key".to_string()));
+        }
+        seed.deserialize(&mut *self.reader).map(Some)
+    }
+
+    fn next_value_seed<V: serde::de::DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, JsonError> {
+        self.reader.expect(b':')?;
+        seed.deserialize(&mut *self.reader)
+    }
+}
@@ -2,414 +2,5839 @@
 //! 
 //! how to use this algorithm:
 //! 
-//! ```
+//! ```no_run
+//! # use apriori::{apriori, write_rules_to_file, AprioriError};
+//! # fn main() -> Result<(), AprioriError> {
 //! // set min_sup and min_conf
 //! let min_sup = 0.005;
 //! let min_conf = 0.3;
 //!
-//! // call the apriori function
-//! let (_fre_sets, association_rules_set) = apriori(min_sup, min_conf, "groceries.csv");
+//! // call the apriori function, a missing or malformed file is an error
+//! let (_fre_sets, association_rules_set) = apriori(min_sup, min_conf, "groceries.csv")?;
 //! 
 //! // write all association rules to file
-//! write_rules_to_file("associationRule.txt", &association_rules_set);
+//! write_rules_to_file("associationRule.txt", &association_rules_set)?;
+//! # Ok(())
+//! # }
 //! ```
+//!
+//! more knobs are available through [`AprioriConfig`]
+
+use std::{collections::{BTreeMap, HashMap, HashSet}, time::{Duration, Instant}, io::Write, mem::size_of_val, ops::ControlFlow};
+
+#[cfg(feature = "serde")]
+mod json;
+#[cfg(feature = "serde")]
+pub use json::create_txn_set_from_json;
+
+#[cfg(feature = "binary")]
+mod binary;
+#[cfg(feature = "binary")]
+pub use binary::{read_rules_bin, write_rules_bin};
+
+/// seconds in one day, timestamps are in seconds while the half-life is in days
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+/// the map used on the hot counting paths, std SipHash unless the fxhash feature is on
+#[cfg(not(feature = "fxhash"))]
+type CountMap<K, V> = HashMap<K, V>;
+#[cfg(feature = "fxhash")]
+type CountMap<K, V> = HashMap<K, V, std::hash::BuildHasherDefault<FxHasher>>;
+
+/// the set used on the hot counting paths, std SipHash unless the fxhash feature is on
+#[cfg(not(feature = "fxhash"))]
+type CountSet<K> = HashSet<K>;
+#[cfg(feature = "fxhash")]
+type CountSet<K> = HashSet<K, std::hash::BuildHasherDefault<FxHasher>>;
+
+/// ## the Fx hash from rustc, much faster than SipHash on short keys like item names
+///
+/// it gives no protection against hash flooding, which is fine for counting items of a dataset
+#[cfg(feature = "fxhash")]
+#[derive(Default)]
+struct FxHasher {
+    hash: u64,
+}
+
+#[cfg(feature = "fxhash")]
+impl FxHasher {
+    const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+    fn add_to_hash(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(FxHasher::SEED);
+    }
+}
+
+#[cfg(feature = "fxhash")]
+impl std::hash::Hasher for FxHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for chunk in bytes.chunks(8) {
+            let mut word = [0; 8];
+            word[..chunk.len()].copy_from_slice(chunk);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
 
-use std::{collections::HashMap, time::SystemTime, io::Write, mem::size_of_val};
+/// # what the items of a transaction can be
+///
+/// String by default, but any ordered and hashable key works, e.g. u32 product codes, which are much cheaper
+/// to hash and clone than their names
+///
+/// Send and Sync let the parallel feature count the CandicateSets and generate the rules on several threads
+///
+/// reading AprioriConfig::ignore_items_file also takes FromStr, a line which does not parse can't be an item
+///
+/// implemented for every type with these bounds, there is nothing to implement by hand
+pub trait Item: Clone + Ord + std::hash::Hash + std::fmt::Debug + Send + Sync {}
+
+impl<T: Clone + Ord + std::hash::Hash + std::fmt::Debug + Send + Sync> Item for T {}
+
+/// the String config, the one AprioriConfig::new gives
+pub type StringApriori = AprioriConfig<String>;
 
 /// # transaction consists of items
 /// 
 /// id: the id of the Txn
 /// 
 /// items: a Vec that contains the items in this transaction
+///
+/// timestamp: when the transaction happened, in seconds since the unix epoch, only used by time decay
+///
+/// weight: how many identical transactions it stands for, 1 unless duplicates were collapsed
 #[derive(Debug)]
 #[allow(dead_code)]
-pub struct Txn {
+pub struct Txn<I = String> {
     id: usize,
-    items: Vec<String>,
+    items: Vec<I>,
+    timestamp: Option<u64>,
+    weight: usize,
+}
+
+impl<I: Item> Txn<I> {
+    /// create a Txn, the items are sorted, in lexicographic order for String
+    pub fn new(id: usize, mut items: Vec<I>) -> Txn<I> {
+        items.sort();
+
+        Txn {
+            id,
+            items,
+            timestamp: None,
+            weight: 1,
+        }
+    }
+
+    /// create a Txn standing for weight identical transactions
+    pub fn with_weight(id: usize, items: Vec<I>, weight: usize) -> Txn<I> {
+        let mut txn = Txn::new(id, items);
+        txn.weight = weight;
+
+        txn
+    }
+
+    /// create a Txn which happened at timestamp (seconds since the unix epoch)
+    pub fn with_timestamp(id: usize, items: Vec<I>, timestamp: u64) -> Txn<I> {
+        let mut txn = Txn::new(id, items);
+        txn.timestamp = Some(timestamp);
+
+        txn
+    }
 }
 
 /// # to be selected as FrequentSet
 /// 
 /// degree: how many items it has
 /// 
-/// items: the Vec of String, containing all the items
+/// items: the Vec of items, containing all the items
 /// 
 /// count: the times the set occurs in all the transactions
+///
+/// weighted_count: the sum of the weights of the transactions containing the set
 #[derive(Debug)]
-pub struct CandicateSet {
+pub struct CandicateSet<I = String> {
     degree: usize,
-    items: Vec<String>,
+    items: Vec<I>,
     count: usize,
+    weighted_count: f64,
+}
+
+impl<I> CandicateSet<I> {
+    /// how many items it has
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// the items, sorted
+    pub fn items(&self) -> &[I] {
+        &self.items
+    }
+
+    /// the number of transactions containing it
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// the sum of the weights of the transactions containing it
+    pub fn weighted_count(&self) -> f64 {
+        self.weighted_count
+    }
 }
 
-/// # CandicateSet whose count is greater than (txn_count * min_sup)
+/// # CandicateSet whose count reaches (txn_count * min_sup)
 /// 
 /// it contains:
 /// 
 /// degree: how many items it has
 /// 
-/// items: the Vec of String, containing all the items
+/// items: the Vec of items, containing all the items
 /// 
 /// count: the times the set occurs in all the transactions
+///
+/// weighted_count: the sum of the weights of the transactions containing the set,
+/// equal to count unless time decay is on
+///
+/// tids: the ids of the transactions containing the set, sorted, only filled with AprioriConfig::track_tids
 #[derive(Clone, Debug)]
-pub struct FrequentSet {
+pub struct FrequentSet<I = String> {
     degree: usize,
-    items: Vec<String>,
+    items: Vec<I>,
     count: usize,
+    weighted_count: f64,
+    tids: Vec<usize>,
+}
+
+impl<I> FrequentSet<I> {
+    /// how many items it has
+    pub fn degree(&self) -> usize {
+        self.degree
+    }
+
+    /// the items, sorted
+    pub fn items(&self) -> &[I] {
+        &self.items
+    }
+
+    /// the number of transactions containing it
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// the sum of the weights of the transactions containing it, equal to count unless time decay is on
+    pub fn weighted_count(&self) -> f64 {
+        self.weighted_count
+    }
+
+    /// count / txn_num, the set does not know how many transactions it was mined from
+    pub fn support(&self, txn_num: usize) -> f64 {
+        self.count as f64 / txn_num as f64
+    }
+
+    /// ## the ids of the transactions containing it, in ascending order
+    ///
+    /// empty unless the run had AprioriConfig::track_tids, a Txn standing for collapsed duplicates has one id,
+    /// so count can be larger than the number of tids
+    pub fn tids(&self) -> &[usize] {
+        &self.tids
+    }
+}
+
+/// `{bread, milk} (count=42)`
+impl<I: std::fmt::Display> std::fmt::Display for FrequentSet<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_braced(f, &self.items)?;
+        write!(f, " (count={})", self.count)
+    }
 }
 
 /// # the final rules we want
 /// 
 /// from -> to
 /// 
-/// with its support and confidence
-#[derive(Debug)]
-pub struct AssociationRule {
-    from: Vec<String>,
-    to: Vec<String>,
+/// with its support, confidence and lift = conf / sup(to)
+///
+/// sup(to) is looked up in fre_sets whether to is one item or more, every subset of a FrequentSet is frequent too,
+/// a lift near 1.0 means from and to are independent and the rule is a coincidence
+///
+/// conviction = (1 - sup(to)) / (1 - conf), how much more often the rule would be wrong if from and to
+/// were independent, f64::INFINITY for a rule which is never wrong (conf = 1)
+///
+/// leverage = sup - sup(from) * sup(to), how much more often from and to occur together than if they were
+/// independent, on the absolute scale of support, in [-0.25, 0.25] and near 0.0 for independence
+///
+/// antecedent_count: the number of transactions containing from, how much data conf is based on
+///
+/// consequent_count: the number of transactions containing to
+///
+/// union_count: the number of transactions containing from ∪ to, so any other metric can be computed
+/// from the three counts and the number of transactions without the FrequentSets
+#[derive(Clone, Debug, PartialEq)]
+pub struct AssociationRule<I = String> {
+    from: Vec<I>,
+    to: Vec<I>,
     sup: f64,
     conf: f64,
+    lift: f64,
+    conviction: f64,
+    leverage: f64,
+    antecedent_count: usize,
+    consequent_count: usize,
+    union_count: usize,
 }
 
-/// the ultimate interface to call apriori function
-/// 
-/// arg
-/// min_sup: minimum support
-/// min_conf: minimum confidence
-/// filename: the dataset's filename, only for csv file now
-/// 
-/// return 
-/// fre_sets: all frequentSet
-/// association_rule_set: all association rules
-pub fn apriori(min_sup: f64, min_conf: f64, filename: &str) -> (Vec<FrequentSet>, Vec<AssociationRule>) {
+impl<I> AssociationRule<I> {
+    /// the antecedent, sorted
+    pub fn from(&self) -> &[I] {
+        &self.from
+    }
 
-    // init
-    // the set of all frequent set, 'sets' means the set of set
-    let mut fre_sets: Vec<FrequentSet> = Vec::new();
-    // generate association rules from fre_sets
-    let mut association_rules_set: Vec<AssociationRule> = Vec::new();
+    /// the consequent, sorted
+    pub fn to(&self) -> &[I] {
+        &self.to
+    }
 
-    // get all transactions from file
-    let mut txn_set = create_sorted_txn_set(filename);    
+    /// the support of from ∪ to
+    pub fn support(&self) -> f64 {
+        self.sup
+    }
 
-    // generate 1-CandicateSet and thus 1-FrequentSet and add it in to the frequent sets
-    init_fre_set(&mut txn_set, min_sup, &mut fre_sets);
-    
-    // the core of the Apriori Algorithm: find frequentSet of all degrees
-    // generate all FrequentSets from 1-FrequentSet
-    generate_all_fre_sets(&mut fre_sets, &txn_set, min_sup);
-    
-    // find all association rules
-    generate_association_rules(&fre_sets, min_conf, &mut association_rules_set, txn_set.len());
+    /// sup(from ∪ to) / sup(from)
+    pub fn confidence(&self) -> f64 {
+        self.conf
+    }
 
-    // repoart space consumption
-    let fre_size: usize = fre_sets.iter().map(|x| size_of_val(&x)).sum();
-    let rules_size: usize  = association_rules_set.iter().map(|x| size_of_val(&x)).sum();
+    /// the number of transactions containing from
+    pub fn antecedent_count(&self) -> usize {
+        self.antecedent_count
+    }
 
-    println!("\nSpace Consumption");
-    println!("The size, len of fre_sets: {} bytes, {}", fre_size, fre_sets.len());
-    println!("The size, len of association_rules_set: {} bytes, {}", rules_size, association_rules_set.len());
+    /// the number of transactions containing to
+    pub fn consequent_count(&self) -> usize {
+        self.consequent_count
+    }
 
-    return (fre_sets, association_rules_set)
-}
+    /// the number of transactions containing from ∪ to
+    pub fn union_count(&self) -> usize {
+        self.union_count
+    }
 
-/// write all association rules to file
-pub fn write_rules_to_file(filename: &str, association_rules_set: &Vec<AssociationRule>) {
-    
-    let mut file = std::fs::File::create(filename).expect("failed to create associationRule.txt");
+    /// conf / sup(to), above 1.0 when from makes to more likely
+    pub fn lift(&self) -> f64 {
+        self.lift
+    }
 
-    for (i, rule) in association_rules_set.iter().enumerate() {
-        file.write_fmt(format_args!("\nrule {}:\n{:#?} --> {:#?}, sup = {}, conf = {}\n", 
-            i, rule.from, rule.to, rule.sup, rule.conf)).expect("failed to write rules to file");
+    /// (1 - sup(to)) / (1 - conf), f64::INFINITY when conf = 1
+    pub fn conviction(&self) -> f64 {
+        self.conviction
+    }
+
+    /// sup - sup(from) * sup(to), above 0.0 when from and to occur together more often than by chance
+    pub fn leverage(&self) -> f64 {
+        self.leverage
     }
 }
 
-/// generate all association rules
-fn generate_association_rules(fre_sets: &Vec<FrequentSet>, min_conf: f64, association_rules_set: &mut Vec<AssociationRule>, txn_num: usize) {
+/// `{bread, milk} => {butter} (sup=0.030, conf=0.410)`
+impl<I: std::fmt::Display> std::fmt::Display for AssociationRule<I> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_braced(f, &self.from)?;
+        f.write_str(" => ")?;
+        write_braced(f, &self.to)?;
+        write!(f, " (sup={:.3}, conf={:.3})", self.sup, self.conf)
+    }
+}
 
-    // start
-    println!("\nStarting to find all Association Rules **********************************************");
-    println!("min_conf: {}", min_conf);
-    let rule_start_time = SystemTime::now();
+/// items separated by commas in braces, the Display of an itemset
+fn write_braced<I: std::fmt::Display>(f: &mut std::fmt::Formatter<'_>, items: &[I]) -> std::fmt::Result {
+    f.write_str("{")?;
 
-    // iterate over non-empty real subset of each FrequentSet
-    for fre_set in fre_sets.iter() {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
 
-        let degree = fre_set.degree;
-        
-        // freset whose degree is 1, is ignored
-        if degree == 1 {
-            continue;
+    f.write_str("}")
+}
+
+/// ## Serialize and Deserialize for a struct of private fields, generic over the item type I
+///
+/// the fields are written under their own names, like serde's derive would, and read back in any order,
+/// unknown fields are skipped
+///
+/// the derived fields are not written but computed from the others on reading, as expressions of them
+///
+/// the encoded fields are written as what encode gives for them, on reading decode gets back what was read,
+/// None for a missing field, and may use the fields
+#[cfg(feature = "serde")]
+macro_rules! serde_struct {
+    ($name:ident { $($field:ident: $ty:ty),* $(,)? }
+        $(derived { $($derived:ident: $value:expr),* $(,)? })?
+        $(encoded { $($encoded:ident: $encoded_ty:ty = $encode:expr, $decode:expr);* $(;)? })?) => {
+        impl<I: serde::Serialize> serde::Serialize for $name<I> {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                use serde::ser::SerializeStruct;
+
+                let mut state = serializer.serialize_struct(stringify!($name), [$(stringify!($field)),* $($(, stringify!($encoded))*)?].len())?;
+                $(state.serialize_field(stringify!($field), &self.$field)?;)*
+                $($(state.serialize_field(stringify!($encoded), &($encode)(&self.$encoded))?;)*)?
+                state.end()
+            }
         }
 
-        // iterate over all subsets of fre_set.items, 2^n -1 in total
-        for mut i in 1..((2 as usize).pow(degree as u32) - 1) {
+        impl<'de, I: serde::Deserialize<'de>> serde::Deserialize<'de> for $name<I> {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                struct FieldsVisitor<I>(std::marker::PhantomData<I>);
 
-            // association rule: from -> to
-            let mut from: Vec<String> = Vec::new();
-            let mut pos = 0;
+                impl<'de, I: serde::Deserialize<'de>> serde::de::Visitor<'de> for FieldsVisitor<I> {
+                    type Value = $name<I>;
 
-            while i != 0 {
+                    fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                        write!(f, "struct {}", stringify!($name))
+                    }
 
-                // bitmap: 1 for from
-                if i&1 == 1 {
-                    from.push(fre_set.items[pos].clone());
-                } 
+                    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<$name<I>, A::Error> {
+                        $(let $field: $ty = seq.next_element()?.ok_or_else(|| serde::de::Error::missing_field(stringify!($field)))?;)*
+                        $($(let $encoded = ($decode)(seq.next_element::<$encoded_ty>()?);)*)?
 
-                pos += 1;
-                i >>= 1;
-            }
+                        Ok($name { $($($derived: $value,)*)? $($($encoded,)*)? $($field),* })
+                    }
 
-            // the rest is for to
-            let to: Vec<String> = fre_set.items.iter().filter(|&x| !from.contains(x)).map(|x| x.to_string()).collect::<Vec<String>>().to_vec();
+                    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<$name<I>, A::Error> {
+                        $(let mut $field: Option<$ty> = None;)*
+                        $($(let mut $encoded: Option<$encoded_ty> = None;)*)?
 
-            // calculate conf for the rule
-            let from_fre_set = fre_sets.iter().find(|&x| x.items.eq(&from)).unwrap();
-            let conf = fre_set.count as f64 / from_fre_set.count as f64;
+                        while let Some(key) = map.next_key::<String>()? {
+                            match key.as_str() {
+                                $(stringify!($field) => $field = Some(map.next_value()?),)*
+                                $($(stringify!($encoded) => $encoded = Some(map.next_value()?),)*)?
+                                _ => {
+                                    map.next_value::<serde::de::IgnoredAny>()?;
+                                },
+                            }
+                        }
 
-            // if conf >= min_conf, this rule is an association rule!
-            if conf >= min_conf {
-                let new_rule = AssociationRule {
-                    from,
-                    to,
-                    sup: fre_set.count as f64 / txn_num as f64,
-                    conf,
-                };
+                        $(let $field = $field.ok_or_else(|| serde::de::Error::missing_field(stringify!($field)))?;)*
+                        $($(let $encoded = ($decode)($encoded);)*)?
+
+                        Ok($name { $($($derived: $value,)*)? $($($encoded,)*)? $($field),* })
+                    }
+                }
 
-                association_rules_set.push(new_rule);
+                deserializer.deserialize_struct(stringify!($name), &[$(stringify!($field)),* $($(, stringify!($encoded))*)?], FieldsVisitor(std::marker::PhantomData))
             }
         }
-    }
-
-    // end
-    let rule_finish_time = SystemTime::now();
-    println!("Finished to find all Association Rules **********************************************");
-    println!("It takes {:#?} to find all frequentSets", rule_finish_time.duration_since(rule_start_time).unwrap());
+    };
 }
 
-/// generate all FrequentSets from 1-FrequentSet
-fn generate_all_fre_sets(fre_sets: &mut Vec<FrequentSet>, txn_set: &Vec<Txn>, min_sup: f64) {
+#[cfg(feature = "serde")]
+serde_struct!(Txn { id: usize, items: Vec<I>, timestamp: Option<u64>, weight: usize });
 
-    // start
-    let fre_start_time = SystemTime::now();
-    println!("\nStarting to find all FrequentSet **********************************************");
-    println!("min_sup: {}, min_count for support: {}\n", min_sup, (txn_set.len() as f64 * min_sup) as usize);
+// the tids are as large as the data they come from, they are not written and are empty on reading
+#[cfg(feature = "serde")]
+serde_struct!(FrequentSet { degree: usize, items: Vec<I>, count: usize, weighted_count: f64 } derived { tids: Vec::new() });
 
-    // calculate min_count from min_sup
-    let min_count = (txn_set.len() as f64 * min_sup) as usize;
+// conviction is infinite for exact rules, which json can't hold, so it is written as null for them,
+// without it, as written before, it follows from the others as sup(to) = conf / lift, up to rounding
+#[cfg(feature = "serde")]
+serde_struct!(AssociationRule { from: Vec<I>, to: Vec<I>, sup: f64, conf: f64, lift: f64, leverage: f64, antecedent_count: usize, consequent_count: usize, union_count: usize }
+    encoded { conviction: Option<f64> = |x: &f64| Some(*x).filter(|x| x.is_finite()), |x: Option<Option<f64>>| match x {
+        Some(written) => written.unwrap_or(f64::INFINITY),
+        None => crate::conviction(conf / lift, conf),
+    } });
 
-    // calculate the len of FrequentSet based on degree
-    let mut degree = 1;
-    let mut len_of_f = len_of_f_degree(&fre_sets, degree);
+/// # the options of one apriori run
+///
+/// built with chained methods, e.g.
+///
+/// ```no_run
+/// # use apriori::AprioriConfig;
+/// let result = AprioriConfig::new()
+///     .min_support(0.01)
+///     .min_confidence(0.3)
+///     .time_decay(30.0)
+///     .run_on_file("groceries.csv");
+/// ```
+///
+/// min_sup: minimum support, 0.05 by default
+///
+/// min_conf: minimum confidence, 0.3 by default
+///
+/// time_decay: the half-life in days of a transaction's weight, None by default
+///
+/// max_item_ratio: distinct items per transaction above which a warning is reported, 1.0 by default
+///
+/// non_redundant: only generate the minimal non-redundant rule basis, false by default
+///
+/// rule_degree_soft_limit: the degree above which only single-item consequents are generated, None by default
+///
+/// ignore_items: items removed from every transaction before mining, empty by default
+///
+/// ignore_items_file: a file of more items to ignore, one per line, None by default
+///
+/// candidate_batch_size: how many CandicateSets are generated and counted at a time, None for all at once by default
+///
+/// max_distinct_items: the number of distinct items above which the run fails, None by default
+///
+/// required_items: only keep FrequentSets with at least one of these items, empty for no constraint by default
+///
+/// forbidden_items: only keep FrequentSets with none of these items, empty by default
+///
+/// membership_only: stop counting a CandicateSet once it is known to be frequent, false by default
+///
+/// trace_itemset: an itemset whose fate is reported in AprioriResult.trace, None by default
+///
+/// retain_transactions: return the mined transactions in AprioriResult.txn_set, false by default
+///
+/// collapse_duplicate_transactions: count identical transactions once with their multiplicity, false by default
+///
+/// targets: the only items allowed in the to of a rule, empty for any item by default
+///
+/// predictors: the only items allowed in the from of a rule, None for any item but the targets by default
+///
+/// bloom_prefilter: reject transactions by a 64-bit item signature before the subset check, false by default
+///
+/// max_degree: the largest FrequentSets mined, None for no limit by default
+///
+/// progress: the callback receiving every ProgressEvent of a run, None to report nothing by default
+///
+/// has_headers: whether the first record of a csv file is a header and skipped, true by default as in groceries.csv
+///
+/// delimiter: the byte between the fields of a csv file, b',' by default
+///
+/// min_count: an absolute support threshold replacing min_sup, None by default
+///
+/// consequent_item: an item the to of every rule must contain, None for any to by default
+///
+/// single_item_consequents: only generate rules whose to is one item, false by default
+///
+/// track_tids: fill FrequentSet.tids with the ids of the transactions containing each set, false by default
+///
+/// normalize_item: the function every item of a csv file is mapped through, None to keep the items as they are by default
+///
+/// max_rules: the most rules kept, the most confident ones, None for no limit by default
+///
+/// AprioriConfig::new is the config of String items, other item types start from default, e.g.
+/// `AprioriConfig::<u32>::default().min_support(0.01).run_on_txn_set(txn_set)` for transactions of u32 codes,
+/// reading a csv file is only for String items
+#[derive(Clone, Debug)]
+pub struct AprioriConfig<I = String> {
+    min_sup: f64,
+    min_conf: f64,
+    time_decay: Option<f64>,
+    max_item_ratio: f64,
+    non_redundant: bool,
+    rule_degree_soft_limit: Option<usize>,
+    ignore_items: Vec<I>,
+    ignore_items_file: Option<String>,
+    parse_item: fn(&str) -> Option<I>,
+    candidate_batch_size: Option<usize>,
+    max_distinct_items: Option<usize>,
+    required_items: Vec<I>,
+    forbidden_items: Vec<I>,
+    membership_only: bool,
+    trace_itemset: Option<Vec<I>>,
+    retain_transactions: bool,
+    collapse_duplicate_transactions: bool,
+    targets: Vec<I>,
+    predictors: Option<Vec<I>>,
+    bloom_prefilter: bool,
+    max_degree: Option<usize>,
+    progress: Option<ProgressCallback>,
+    has_headers: bool,
+    delimiter: u8,
+    min_count: Option<usize>,
+    consequent_item: Option<I>,
+    single_item_consequents: bool,
+    track_tids: bool,
+    normalize_item: Option<ItemNormalizer>,
+    max_rules: Option<usize>,
+    owned_bytes: fn(&I) -> usize,
+    count_rules_only: bool,
+}
 
-    // when f of degree is empty, the loop is over
-    while len_of_f > 0 {
-        println!("degree: {}, num of corresponding frequentSet: {}", degree, len_of_f);
+impl<I> Default for AprioriConfig<I> {
+    fn default() -> Self {
+        AprioriConfig {
+            min_sup: 0.05,
+            min_conf: 0.3,
+            time_decay: None,
+            max_item_ratio: 1.0,
+            non_redundant: false,
+            rule_degree_soft_limit: None,
+            ignore_items: Vec::new(),
+            ignore_items_file: None,
+            parse_item: |_| None,
+            candidate_batch_size: None,
+            max_distinct_items: None,
+            required_items: Vec::new(),
+            forbidden_items: Vec::new(),
+            membership_only: false,
+            trace_itemset: None,
+            retain_transactions: false,
+            collapse_duplicate_transactions: false,
+            targets: Vec::new(),
+            predictors: None,
+            bloom_prefilter: false,
+            max_degree: None,
+            progress: None,
+            has_headers: true,
+            delimiter: b',',
+            min_count: None,
+            consequent_item: None,
+            single_item_consequents: false,
+            track_tids: false,
+            normalize_item: None,
+            max_rules: None,
+            owned_bytes: |_| 0,
+            count_rules_only: false,
+        }
+    }
+}
 
-        // candi_sets.count = 0 at this moment
-        // len of set in candi_sets is degree + 1
-        let candi_sets: Vec<CandicateSet> = get_candi_from_f(&fre_sets, degree);
+impl AprioriConfig {
+    /// create a config with the default options, for String items
+    pub fn new() -> AprioriConfig {
+        AprioriConfig { owned_bytes: String::capacity, ..AprioriConfig::default() }
+    }
 
-        for mut candi_set in candi_sets {
-            
-            for txn in txn_set.iter() {
+    /// run apriori on a csv file
+    pub fn run_on_file(&self, filename: &str) -> Result<AprioriResult, AprioriError> {
+        // get all transactions from file
+        let load_start_time = Instant::now();
+        let txn_set = create_sorted_txn_set(filename, self)?;
+        let load_duration = load_start_time.elapsed();
 
-                // if candi_set.items is subset of txn.items
-                // candi_set.count += 1
-                if subset_of(&candi_set.items, &txn.items) {
-                    candi_set.count += 1;
-                }
-            }
+        self.run(txn_set, load_duration)
+    }
 
-            // if candi_set.items >= min_sup
-            // convert it to FrequentSet and add it to fre_sets
-            if candi_set.count >= min_count {
-                let new_fre = FrequentSet {
-                    degree: candi_set.degree,
-                    items: candi_set.items,
-                    count: candi_set.count,
-                };
+    /// ## run apriori on csv data from any reader
+    ///
+    /// the same as run_on_file for data which is not a plain file, e.g. a gzipped file through a decoder,
+    /// there is no gzip reader in this crate, with flate2 it is
+    /// `config.run_on_reader(flate2::read::GzDecoder::new(std::fs::File::open("groceries.csv.gz")?))`
+    pub fn run_on_reader<R: std::io::Read>(&self, reader: R) -> Result<AprioriResult, AprioriError> {
+        let load_start_time = Instant::now();
+        let txn_set = csv_txns(reader, self).collect::<Result<Vec<Txn>, AprioriError>>()?;
+        let load_duration = load_start_time.elapsed();
 
-                fre_sets.push(new_fre);
-            }
-        }
+        self.run(txn_set, load_duration)
+    }
 
-        degree += 1;
-        len_of_f = len_of_f_degree(&fre_sets, degree);
+    /// ## run the full pipeline on a csv file only to measure it
+    ///
+    /// the frequent sets are mined as usual, the rules are generated and counted but never collected,
+    /// so Timings.rules is the cost of finding them without the one of holding and sorting them,
+    /// nothing is written and only the timings are returned
+    ///
+    /// ProgressEvent::RulesFinished still reports how many rules there are, with non_redundant the basis
+    /// is built as usual, then dropped
+    ///
+    /// meant for ad-hoc profiling where the output is not needed
+    pub fn benchmark_file(&self, filename: &str) -> Result<Timings, AprioriError> {
+        let mut config = self.clone();
+        config.count_rules_only = true;
 
+        Ok(config.run_on_file(filename)?.timings)
     }
 
-    // end
-    let fre_finish_time = SystemTime::now();
-    println!("\nFinished to find all FrequentSet **********************************************");
-    println!("It takes {:#?} to find all frequentSets", fre_finish_time.duration_since(fre_start_time).unwrap());
+    /// ## whether the first record of the csv file is a header
+    ///
+    /// a header is skipped, without one the first record is the first transaction,
+    /// turn it off for exports which start right with the data
+    pub fn has_headers(mut self, has_headers: bool) -> AprioriConfig {
+        self.has_headers = has_headers;
+        self
+    }
+
+    /// ## the byte between the fields of the csv file
+    ///
+    /// b'\t' reads tsv files and b';' semicolon-separated exports, comma by default
+    pub fn delimiter(mut self, delimiter: u8) -> AprioriConfig {
+        self.delimiter = delimiter;
+        self
+    }
 
+    /// ## map every item of the csv file through normalize before it is counted
+    ///
+    /// `normalize_items(str::to_lowercase)` makes Milk, milk and MILK one item, a closure can also strip
+    /// punctuation or map synonyms to one name, the items are trimmed before and dropped if empty after,
+    /// ignore_items and every other item given to the config should be written in the normalized form
+    ///
+    /// transactions already in memory are not normalized
+    pub fn normalize_items<F: Fn(&str) -> String + Send + Sync + 'static>(mut self, normalize: F) -> AprioriConfig {
+        self.normalize_item = Some(ItemNormalizer(std::sync::Arc::new(normalize)));
+        self
+    }
 }
 
-/// generate 1-CandicateSet and thus 1-FrequentSet and add it in to the frequent sets
-fn init_fre_set(txn_set: &mut Vec<Txn>, min_sup: f64, fre_sets: &mut Vec<FrequentSet>) {
-    // generate C_1
-    let candicate_set_1 = create_candicate_set_1(&txn_set);
-    // generate F_1
-    let min_count = (txn_set.len() as f64 * min_sup) as usize;
-    let frequent_set_1 = create_frequent_set_1(candicate_set_1, min_count);
+impl<I: Item + std::str::FromStr> AprioriConfig<I> {
+    /// ## remove the items listed in a file from every transaction before mining
+    ///
+    /// the file has one item per line, surrounding whitespace and empty lines are skipped
+    ///
+    /// it is read when the run starts, and adds to the items given to ignore_items
+    ///
+    /// a line which does not parse as an item is skipped
+    pub fn ignore_items_file(mut self, filename: &str) -> AprioriConfig<I> {
+        self.ignore_items_file = Some(filename.to_string());
+        self.parse_item = |x| x.parse().ok();
+        self
+    }
+}
 
-    // add F_1 to fre_set
-    for set in frequent_set_1.iter() {
-        let fre_set = FrequentSet {
-            degree: 1,
-            items: vec![set.0.clone()],
-            count: set.1.clone(),
-        };
+impl<I: Item> AprioriConfig<I> {
+    /// set minimum support, replacing a min_support_count set before
+    pub fn min_support(mut self, min_sup: f64) -> AprioriConfig<I> {
+        self.min_sup = min_sup;
+        self.min_count = None;
+        self
+    }
 
-        fre_sets.push(fre_set);
+    /// ## set minimum support as the number of transactions an itemset must appear in
+    ///
+    /// min_count is used as it is instead of txn_count * min_sup, replacing a min_support set before,
+    /// under time decay the weighted count must reach the same fraction min_count / txn_count of the total weight
+    pub fn min_support_count(mut self, min_count: usize) -> AprioriConfig<I> {
+        self.min_count = Some(min_count);
+        self
     }
-}
 
-/// judge whether a set is a subset of another set
-/// 
-/// this fn can be expanded to generics in the future
-fn subset_of(subset: &Vec<String>, set: &Vec<String>) -> bool {
+    /// set minimum confidence
+    pub fn min_confidence(mut self, min_conf: f64) -> AprioriConfig<I> {
+        self.min_conf = min_conf;
+        self
+    }
 
-    for item in subset {
-        if !set.contains(item) {
-            return false;
-        }
+    /// ## weight transactions by recency
+    ///
+    /// with a half-life of h days, a transaction which is `age` days older than the newest
+    /// transaction contributes exp(-lambda * age) to support instead of 1, where lambda = ln(2) / h
+    ///
+    /// under decay, support is the share of the total weight rather than of the number of transactions:
+    ///
+    /// sup(X) = sum of weights of txns containing X / sum of weights of all txns
+    ///
+    /// an itemset is frequent when sup(X) >= min_sup, and conf(X -> Y) = sup(X ∪ Y) / sup(X)
+    ///
+    /// FrequentSet.count still holds the raw number of occurrences, the decayed one is weighted_count
+    ///
+    /// transactions without timestamp are treated as the newest ones, i.e. weight 1
+    pub fn time_decay(mut self, half_life_days: f64) -> AprioriConfig<I> {
+        self.time_decay = Some(half_life_days);
+        self
     }
 
-    true
-}
+    /// ## warn when there are suspiciously many distinct items
+    ///
+    /// more distinct items than max_item_ratio * txn_count is almost always a parsing problem,
+    /// e.g. a wrong delimiter or ids used as items
+    ///
+    /// the run still goes on and reports Warning::HighItemCardinality in AprioriResult.warnings
+    pub fn item_cardinality_warning(mut self, max_item_ratio: f64) -> AprioriConfig<I> {
+        self.max_item_ratio = max_item_ratio;
+        self
+    }
 
-/// generate set of K-CandicateSet from set of (K-1)-FrequentSet
-/// 
-/// if two (K-1)-FrequentSets, the first K-2 elements are identical and the (k-1)th are different
-/// 
-/// then generate new CandicateSet with (degree - 1) elements and the degree-th element 
-fn get_candi_from_f(fre_sets: &Vec<FrequentSet>, degree: usize) -> Vec<CandicateSet> {
+    /// ## generate the minimal non-redundant rule basis instead of all rules
+    ///
+    /// see [`non_redundant_rules`], the confidence of these rules is based on the raw counts even under time decay
+    pub fn non_redundant_rules(mut self, non_redundant: bool) -> AprioriConfig<I> {
+        self.non_redundant = non_redundant;
+        self
+    }
 
-    let mut candi:Vec<CandicateSet> = Vec::new();
+    /// ## bound the rule generation of very large FrequentSets
+    ///
+    /// all 2^degree - 2 splits of a FrequentSet are tried when generating rules, which hangs on
+    /// pathological data where a FrequentSet reaches a large degree
+    ///
+    /// above the soft limit, only the degree rules with a single item as to are tried for that set,
+    /// and ProgressEvent::RuleDegreeSoftLimit is reported
+    ///
+    /// None tries all splits whatever the degree, as by default, Some(20) keeps a run to about a million splits per set
+    pub fn rule_degree_soft_limit(mut self, limit: Option<usize>) -> AprioriConfig<I> {
+        self.rule_degree_soft_limit = limit;
+        self
+    }
 
-    // fre_sets is a set of frequent_set with all kinds of degrees
-    // get degree-frequent_sets which is a set of frequent_set with degree items
-    let degree_fre_sets = get_degree_fre_sets(fre_sets, degree);
+    /// remove these items from every transaction before mining
+    pub fn ignore_items(mut self, items: Vec<I>) -> AprioriConfig<I> {
+        self.ignore_items = items;
+        self
+    }
 
-    for i in 0..(degree_fre_sets.len() - 1) {
-        for j in i+1..degree_fre_sets.len() {
+    /// ## generate and count CandicateSets in batches to bound memory
+    ///
+    /// by default all CandicateSets of a degree are generated before counting, which spikes memory at low support
+    ///
+    /// with a batch size, at most batch_size of them are held at once, the FrequentSets found are the same,
+    /// smaller batches only cost the bookkeeping of resuming the join
+    pub fn candidate_batch_size(mut self, batch_size: usize) -> AprioriConfig<I> {
+        self.candidate_batch_size = Some(batch_size);
+        self
+    }
 
-            // the first (degree -1) elements are identical
-            if degree_fre_sets[i].items.as_slice()[0..(degree-1)] == degree_fre_sets[j].items.as_slice()[0..(degree-1)] {
+    /// ## fail when there are more distinct items than this
+    ///
+    /// a safety valve against mining the wrong column, e.g. free text used as items,
+    /// which makes even the 1-itemset counting heavy
+    ///
+    /// checked right after counting the 1-itemsets, the run returns AprioriError::TooManyDistinctItems
+    pub fn max_distinct_items(mut self, max_distinct_items: usize) -> AprioriConfig<I> {
+        self.max_distinct_items = Some(max_distinct_items);
+        self
+    }
 
-                // the degree-th elements are different
-                if degree_fre_sets[i].items.get(degree - 1).unwrap() != degree_fre_sets[j].items.get(degree - 1).unwrap() {
+    /// ## only keep FrequentSets containing at least one of these items
+    ///
+    /// apriori builds every set from its subsets, and the subsets without a required item are still needed,
+    /// both to be joined into larger sets and to look up the support of antecedents when generating rules
+    ///
+    /// so they are mined as usual and only dropped at the end, this focuses the output, not the mining,
+    /// rules are only generated from the kept sets
+    ///
+    /// AprioriResult.fre_sets then lacks those subsets, the helpers reading FrequentSets skip the rules and pairs
+    /// whose supports they can't look up, maximal_frequent_sets and closed_frequent_sets need the full lattice
+    pub fn required_items(mut self, items: Vec<I>) -> AprioriConfig<I> {
+        self.required_items = items;
+        self
+    }
 
-                    // then generate new CandicateSet with (degree - 1) elements and the degree-th element
-                    // generate items
-                    let mut items = degree_fre_sets[i].items.clone();
-                    items.push(degree_fre_sets[j].items.get(degree - 1).unwrap().clone());
+    /// ## only keep FrequentSets containing none of these items
+    ///
+    /// no superset of a set with a forbidden item can be kept, so these items are pruned from the
+    /// 1-FrequentSets and no CandicateSet containing them is ever generated or counted
+    ///
+    /// unlike ignore_items, the transactions are left as they are
+    pub fn forbidden_items(mut self, items: Vec<I>) -> AprioriConfig<I> {
+        self.forbidden_items = items;
+        self
+    }
 
-                    let count = 0;
+    /// ## only find which itemsets are frequent, not their exact support
+    ///
+    /// a CandicateSet stops being counted as soon as its count reaches min_count, which skips the rest
+    /// of the scan for it, the FrequentSets found are the same as without this mode
+    ///
+    /// but the count and weighted_count of the FrequentSets above degree 1 are underreported, stuck near the threshold,
+    /// so the support and confidence of the rules are meaningless, only use it when the sets themselves are needed
+    pub fn membership_only(mut self, membership_only: bool) -> AprioriConfig<I> {
+        self.membership_only = membership_only;
+        self
+    }
 
-                    let new_candi = CandicateSet {
-                        degree: degree + 1,
-                        items,
-                        count,
-                    };
+    /// ## follow one itemset through the run, to find out why an expected rule is missing
+    ///
+    /// the run then reports in AprioriResult.trace whether the itemset was generated as a CandicateSet,
+    /// its count against min_count, and the confidence of every rule it gives, see [`ItemsetTrace`]
+    pub fn trace_itemset(mut self, mut items: Vec<I>) -> AprioriConfig<I> {
+        items.sort();
+        items.dedup();
+        self.trace_itemset = Some(items);
+        self
+    }
 
-                    candi.push(new_candi);
-                }
-            }
-        }
+    /// ## keep the transactions after mining and return them in AprioriResult.txn_set
+    ///
+    /// for the helpers working on transactions, like exact_support or contingency_table,
+    /// without reading the file again
+    ///
+    /// they are the transactions as mined, i.e. without the ignored items, and hold the whole
+    /// dataset in memory for as long as the result lives, about the size of the file
+    pub fn retain_transactions(mut self, retain: bool) -> AprioriConfig<I> {
+        self.retain_transactions = retain;
+        self
     }
 
-    candi
-}
+    /// ## merge identical transactions into one Txn with a weight
+    ///
+    /// transactions with the same items and timestamp become one Txn whose weight is how many they were,
+    /// the counting adds the weight instead of 1, which is much faster when few baskets are distinct
+    ///
+    /// counts and supports are exactly the same as without collapsing: the support denominator is
+    /// the sum of the weights, i.e. still the number of original transactions
+    ///
+    /// the ids of the merged transactions are lost, the first one is kept
+    pub fn collapse_duplicate_transactions(mut self, collapse: bool) -> AprioriConfig<I> {
+        self.collapse_duplicate_transactions = collapse;
+        self
+    }
 
-/// fre_sets is a set of frequent_set with all kinds of degrees
-/// 
-/// get degree-frequent_sets which is a set of frequent_set with degree items
-fn get_degree_fre_sets(fre_sets: &Vec<FrequentSet>, degree: usize) -> Vec<FrequentSet> {
-    fre_sets.clone().into_iter().filter(|x| x.degree == degree).collect::<Vec<FrequentSet>>().to_vec()
-}
+    /// ## only generate rules predicting these items
+    ///
+    /// the to of every rule is made of targets only, and targets never appear in a from,
+    /// as in associative classification where the targets are the classes
+    ///
+    /// FrequentSets are mined as usual, only the rules are restricted
+    pub fn targets(mut self, items: Vec<I>) -> AprioriConfig<I> {
+        self.targets = items;
+        self
+    }
 
-/// ## get len of f based on the degree
-/// 
-/// the fre_sets is a set of all FrequentSet, degree indicates the number of item in each FrequentSet
-fn len_of_f_degree(fre_sets: &Vec<FrequentSet> , degree: usize) -> usize {
-    fre_sets.iter().filter(|x| x.degree == degree).count() as usize
-}
+    /// ## only generate rules whose from is made of these items
+    ///
+    /// without predictors, every item which is not a target can be in a from
+    ///
+    /// with both targets and predictors, an item which is neither never appears in a rule,
+    /// so a FrequentSet containing such an item gives no rule at all
+    pub fn predictors(mut self, items: Vec<I>) -> AprioriConfig<I> {
+        self.predictors = Some(items);
+        self
+    }
 
-/// ## generate frequent_set_1, given clone of candicate_set_1 and min_count
-/// 
-/// if candicate_set's count is larger than len(txn_set) * min_sup, the set is frequent
-/// 
-/// to avoid changing the value in candicate_set_1, use clone of it
-/// 
-/// min_count is calculated by multiplying length of txn_set and min_sup
-fn create_frequent_set_1(candicate_set_1: HashMap<String, usize>, min_count: usize) -> HashMap<String, usize> {
-    let frequent_set_1: HashMap<String, usize> 
-        = candicate_set_1.into_iter()
-            .filter(|x| x.1.clone()  >  min_count)
-            .collect::<HashMap<_,_>>();
+    /// ## only generate rules whose to contains item, e.g. what leads to buying wine
+    ///
+    /// the FrequentSets without item are skipped when generating rules, so their splits are never tried,
+    /// an item which is not frequent gives no rule at all
+    pub fn consequent_must_contain(mut self, item: I) -> AprioriConfig<I> {
+        self.consequent_item = Some(item);
+        self
+    }
 
-    frequent_set_1
-}
+    /// ## only generate rules with exactly one item in their to, the usual market basket rules
+    ///
+    /// the from can still have any number of items, only the degree splits of each FrequentSet are tried
+    /// instead of 2^degree - 2, as above rule_degree_soft_limit
+    pub fn single_item_consequents(mut self, single_item_consequents: bool) -> AprioriConfig<I> {
+        self.single_item_consequents = single_item_consequents;
+        self
+    }
 
-/// ## generate candicate_set_1 from txn_set
-/// 
-/// candicate_set_1 is a hashmap which contains only one item(String) and its corresponding count
-/// 
-/// txn_set: Vec of Txn
-/// 
-/// By using hashMap, we can create candicate_set_1 conveniently
-fn create_candicate_set_1(txn_set: &Vec<Txn>) -> HashMap<String, usize> {
-    
-    // generate C_1
-    let mut candicate_set_1: HashMap<String, usize> = HashMap::new();
-    for txn in txn_set.iter() {
+    /// ## record which transactions contain every FrequentSet, see FrequentSet::tids
+    ///
+    /// the tids of the items come from one more scan of the transactions, the tids of a larger set are
+    /// the intersection of the tids of the two sets it was joined from, so nothing is rescanned,
+    /// but every FrequentSet holds a Vec as long as its count
+    pub fn track_tids(mut self, track_tids: bool) -> AprioriConfig<I> {
+        self.track_tids = track_tids;
+        self
+    }
 
-        for item in txn.items.clone() {
-            candicate_set_1.entry(item).and_modify(|x| *x += 1).or_insert(1);
-        }
+    /// ## skip most non-matching transactions before the subset check
+    ///
+    /// every transaction gets a one-word bloom filter of its items, a candidate whose bits
+    /// are not all set in it can't be a subset and the transaction is skipped
+    ///
+    /// the filter only has false positives, so it never misses a real match and the counts are the same,
+    /// it pays off on very sparse data where most transactions contain few of the candidate's items,
+    /// on dense data most bits are set and it only adds the cost of the signatures
+    pub fn bloom_prefilter(mut self, bloom_prefilter: bool) -> AprioriConfig<I> {
+        self.bloom_prefilter = bloom_prefilter;
+        self
     }
 
-    candicate_set_1
-}
+    /// ## only mine FrequentSets of at most max_degree items
+    ///
+    /// no CandicateSet above max_degree is generated, even when larger FrequentSets exist,
+    /// and the rules only come from the sets mined, which bounds the memory and time of low min_sup runs
+    pub fn max_degree(mut self, max_degree: usize) -> AprioriConfig<I> {
+        self.max_degree = Some(max_degree);
+        self
+    }
 
-/// ## generate txn_set from csv file, the items in each txn are sorted in lexicographic order
-/// 
-/// filename: the path and name of the dataset.csv
-fn create_sorted_txn_set(filename: &str) -> Vec<Txn> {
+    /// ## keep only the max_rules most confident rules, in the order of sort_rules_by_confidence
+    ///
+    /// the rules go into a heap bounded to max_rules as they are generated, like top_rules, so a low min_conf
+    /// can't fill the memory with rules, with non_redundant_rules they are only cut after all are generated
+    pub fn max_rules(mut self, max_rules: usize) -> AprioriConfig<I> {
+        self.max_rules = Some(max_rules);
+        self
+    }
 
-    let mut txn_set: Vec<Txn> = Vec::new();
+    /// ## report the progress of a run to callback
+    ///
+    /// the callback is called on the thread of the run, once for every ProgressEvent,
+    /// without it nothing is printed or reported
+    pub fn progress<F: Fn(ProgressEvent) + Send + Sync + 'static>(mut self, callback: F) -> AprioriConfig<I> {
+        self.progress = Some(ProgressCallback(std::sync::Arc::new(callback)));
+        self
+    }
+
+    /// run apriori on transactions already in memory
+    pub fn run_on_txn_set(&self, txn_set: Vec<Txn<I>>) -> Result<AprioriResult<I>, AprioriError> {
+        self.run(txn_set, Duration::ZERO)
+    }
 
-    let mut reader = csv::Reader::from_path(filename).expect("failed to read the csv file");
+    /// run apriori on transactions given as their items, sorted like the transactions of a file
+    pub fn run_on_transactions(&self, transactions: Vec<Vec<I>>) -> Result<AprioriResult<I>, AprioriError> {
+        let txn_set: Vec<Txn<I>> = transactions.into_iter().enumerate().map(|(i, items)| Txn::new(i, items)).collect();
 
-    for (i, items_result) in reader.records().enumerate() {
-        let items = items_result.expect("faile to get items from txn_result");
+        self.run_on_txn_set(txn_set)
+    }
 
-        let mut items_vec: Vec<String> = items.iter().filter(|x| !x.is_empty()).map(|x| x.to_string()).collect();
+    /// ignore_items together with the items of ignore_items_file
+    fn ignored_items(&self) -> Result<CountSet<I>, AprioriError> {
+        let mut ignored: CountSet<I> = self.ignore_items.iter().cloned().collect();
 
-        // sort the items for each txn in txn_set in lexicographic order
-        items_vec.sort();
+        if let Some(filename) = &self.ignore_items_file {
+            let content = std::fs::read_to_string(filename)?;
+            ignored.extend(content.lines().map(|x| x.trim()).filter(|x| !x.is_empty()).filter_map(self.parse_item));
+        }
+
+        Ok(ignored)
+    }
+
+    /// hand event to the progress callback, if any
+    fn report(&self, event: ProgressEvent) {
+        if let Some(ProgressCallback(callback)) = &self.progress {
+            callback(event);
+        }
+    }
+
+    /// min_sup and min_conf must both be in (0.0, 1.0]
+    fn check_thresholds(&self) -> Result<(), AprioriError> {
+        for (name, value) in [("min_sup", self.min_sup), ("min_conf", self.min_conf)] {
+            if !(value > 0.0 && value <= 1.0) {
+                return Err(AprioriError::InvalidThreshold { name, value });
+            }
+        }
 
-        let txn = Txn {
-            id: i,
-            items: items_vec,
+        Ok(())
+    }
+
+    /// whether the split from -> to satisfies targets, predictors, consequent_item and single_item_consequents
+    fn allows_split(&self, from: &[I], to: &[I]) -> bool {
+        let from_allowed = match &self.predictors {
+            Some(predictors) => from.iter().all(|x| predictors.contains(x)),
+            None => !from.iter().any(|x| self.targets.contains(x)),
         };
-        
-        txn_set.push(txn);
+
+        from_allowed
+            && (self.targets.is_empty() || to.iter().all(|x| self.targets.contains(x)))
+            && self.consequent_item.as_ref().is_none_or(|item| to.contains(item))
+            && (!self.single_item_consequents || to.len() == 1)
     }
 
-    txn_set
-}
+    /// whether items satisfies required_items
+    fn has_required_item(&self, items: &[I]) -> bool {
+        self.required_items.is_empty() || items.iter().any(|x| self.required_items.contains(x))
+    }
 
-/// get good filename based on min_sup and min_conf
-pub fn get_good_filename(min_sup: f64, min_conf: f64) -> String {
-    let mut filename: String = "associationRule_".to_string();
-    filename += &min_sup.to_string();
-    filename += "_";
-    filename += &min_conf.to_string();
-    filename += ".txt";
+    /// the pipeline behind run_on_file and run_on_txn_set
+    fn run(&self, mut txn_set: Vec<Txn<I>>, load_duration: Duration) -> Result<AprioriResult<I>, AprioriError> {
 
-    filename
-}
\ No newline at end of file
+        // init
+        // the set of all frequent set, 'sets' means the set of set
+        let mut fre_sets: Vec<FrequentSet<I>> = Vec::new();
+        // generate association rules from fre_sets
+        let mut association_rules_set: Vec<AssociationRule<I>> = Vec::new();
+        // non-fatal problems found on the way
+        let mut warnings: Vec<Warning> = Vec::new();
+
+        self.check_thresholds()?;
+
+        // drop the ignored items before anything is counted
+        let ignored = self.ignored_items()?;
+        if !ignored.is_empty() {
+            for txn in txn_set.iter_mut() {
+                txn.items.retain(|x| !ignored.contains(x));
+            }
+        }
+
+        if self.collapse_duplicate_transactions {
+            txn_set = collapse_duplicates(txn_set);
+        }
+
+        // catch misparsed datasets early
+        warnings.extend(check_item_cardinality(&txn_set, self.max_item_ratio));
+
+        // the contribution of each txn to support
+        let weights = txn_weights(&txn_set, self.time_decay);
+        let txn_num = txn_count(&txn_set);
+        let min_sup = match self.min_count {
+            Some(min_count) => MinSupport::from_count(min_count, txn_num, &weights, self.time_decay.is_some()),
+            None => MinSupport::new(self.min_sup, txn_num, &weights, self.time_decay.is_some()),
+        };
+        match self.min_count {
+            Some(0) if txn_num > 0 => return Err(AprioriError::ZeroMinSupportCount),
+            Some(_) => {},
+            None => min_sup.check_nonzero(self.min_sup, txn_num)?,
+        }
+
+        // generate 1-CandicateSet and thus 1-FrequentSet and add it in to the frequent sets
+        let candi_num_1 = init_fre_set(&mut txn_set, &weights, &min_sup, self.max_distinct_items, &mut fre_sets)?;
+        let mut candidate_counts: Vec<(usize, usize)> = vec![(1, candi_num_1)];
+
+        // the core of the Apriori Algorithm: find frequentSet of all degrees
+        // generate all FrequentSets from 1-FrequentSet
+        let mut traced_count: Option<usize> = None;
+        let fre_duration = generate_all_fre_sets(&mut fre_sets, &txn_set, &weights, &min_sup, self, &mut traced_count, &mut candidate_counts);
+        sort_fre_sets(&mut fre_sets);
+
+        if self.track_tids {
+            fill_tids(&mut fre_sets, &txn_set);
+        }
+
+        // tell why the rules will be empty
+        if !fre_sets.iter().any(|x| x.degree >= 2) {
+            let applied = if self.min_count.is_some() { min_sup.effective(txn_num) } else { self.min_sup };
+            warnings.push(Warning::NoMultiItemPatterns { min_sup: applied });
+        }
+
+        // find all association rules
+        let rule_duration = if self.non_redundant {
+            let rule_start_time = Instant::now();
+            association_rules_set = non_redundant_rules(&fre_sets, self.min_conf, txn_count(&txn_set));
+            association_rules_set.retain(|x| self.has_required_item(&x.from) || self.has_required_item(&x.to));
+            association_rules_set.retain(|x| self.allows_split(&x.from, &x.to));
+            sort_rules_by_confidence(&mut association_rules_set);
+            association_rules_set.truncate(self.max_rules.unwrap_or(usize::MAX));
+            if self.count_rules_only {
+                association_rules_set.clear();
+            }
+            rule_start_time.elapsed()
+        } else {
+            generate_association_rules(&fre_sets, self, &mut association_rules_set, min_sup.total_weight)
+        };
+
+        let trace = self.trace_itemset.as_ref()
+            .map(|items| trace_itemset(items, &txn_set, &fre_sets, &min_sup, traced_count));
+
+        // the sets without a required item were only kept for the lookups above
+        fre_sets.retain(|x| self.has_required_item(&x.items));
+
+        // repoart space consumption
+        let memory = MemoryUsage {
+            fre_sets_bytes: fre_sets.iter().map(|x| fre_set_bytes(x, self.owned_bytes)).sum(),
+            rules_bytes: association_rules_set.iter().map(|x| rule_bytes(x, self.owned_bytes)).sum(),
+        };
+
+        self.report(ProgressEvent::SpaceConsumption {
+            fre_sets_bytes: memory.fre_sets_bytes,
+            fre_sets: fre_sets.len(),
+            rules_bytes: memory.rules_bytes,
+            rules: association_rules_set.len(),
+        });
+
+        Ok(AprioriResult {
+            fre_sets,
+            association_rules_set,
+            warnings,
+            timings: Timings {
+                load: load_duration,
+                frequent_sets: fre_duration,
+                rules: rule_duration,
+            },
+            candidate_counts,
+            effective_min_sup: min_sup.effective(txn_num),
+            trace,
+            txn_set: if self.retain_transactions { Some(txn_set) } else { None },
+            memory,
+        })
+    }
+}
+
+/// # everything one apriori run produces
+///
+/// fre_sets: all frequentSet, sorted by degree then items, see sort_fre_sets
+///
+/// association_rules_set: all association rules
+///
+/// warnings: non-fatal problems found during the run
+///
+/// timings: how long each stage took
+///
+/// candidate_counts: the (degree, number of CandicateSets counted) of every degree tried
+///
+/// effective_min_sup: the support threshold really applied, min_count / txn_count, a bit below min_sup since
+/// min_count = txn_count * min_sup is truncated
+///
+/// trace: the fate of the itemset given to trace_itemset, None without it
+///
+/// txn_set: the mined transactions with retain_transactions, None without it
+///
+/// memory: the estimated bytes held by fre_sets and association_rules_set
+#[derive(Debug)]
+pub struct AprioriResult<I = String> {
+    pub fre_sets: Vec<FrequentSet<I>>,
+    pub association_rules_set: Vec<AssociationRule<I>>,
+    pub warnings: Vec<Warning>,
+    pub timings: Timings,
+    pub candidate_counts: Vec<(usize, usize)>,
+    pub effective_min_sup: f64,
+    pub trace: Option<ItemsetTrace<I>>,
+    pub txn_set: Option<Vec<Txn<I>>>,
+    pub memory: MemoryUsage,
+}
+
+impl<I> AprioriResult<I> {
+    /// the timings and counts of the run, for logging and benchmarking
+    pub fn stats(&self) -> AprioriStats {
+        AprioriStats {
+            frequent_set_duration: self.timings.frequent_sets,
+            rule_duration: self.timings.rules,
+            num_frequent_sets: self.fre_sets.len(),
+            num_rules: self.association_rules_set.len(),
+            num_candidates_evaluated: self.candidate_counts.iter().map(|x| x.1).sum(),
+        }
+    }
+}
+
+/// # the headline numbers of a run
+///
+/// frequent_set_duration: how long finding the FrequentSets of degree 2 and more took
+///
+/// rule_duration: how long generating the association rules took
+///
+/// num_frequent_sets: the number of FrequentSets of all degrees
+///
+/// num_rules: the number of association rules
+///
+/// num_candidates_evaluated: the CandicateSets counted over all degrees, the distinct items for degree 1
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AprioriStats {
+    pub frequent_set_duration: Duration,
+    pub rule_duration: Duration,
+    pub num_frequent_sets: usize,
+    pub num_rules: usize,
+    pub num_candidates_evaluated: usize,
+}
+
+/// # the memory held by the FrequentSets and the rules of a run
+///
+/// every struct plus the buffers of its item Vecs, and the bytes of String items for a config from AprioriConfig::new,
+/// the heap owned by other item types is not known and left out
+///
+/// an estimate from the capacities, the allocator may round every allocation up
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryUsage {
+    pub fre_sets_bytes: usize,
+    pub rules_bytes: usize,
+}
+
+/// # what happened to the itemset traced with AprioriConfig::trace_itemset
+///
+/// items: the itemset, sorted
+///
+/// count: the number of transactions containing it
+///
+/// min_count: the count it needed to be frequent, under time decay the threshold is on the weighted count instead
+///
+/// candidate_count: the count it reached as a CandicateSet, None when it was never generated,
+/// which happens when some subset one item smaller is not frequent
+///
+/// infrequent_subsets: the subsets one item smaller which are not frequent
+///
+/// frequent: whether it is a FrequentSet
+///
+/// splits: every from -> to split of a frequent itemset with its confidence,
+/// whether the confidence reached min_conf or not
+#[derive(Clone, Debug)]
+pub struct ItemsetTrace<I = String> {
+    pub items: Vec<I>,
+    pub count: usize,
+    pub min_count: usize,
+    pub candidate_count: Option<usize>,
+    pub infrequent_subsets: Vec<Vec<I>>,
+    pub frequent: bool,
+    pub splits: Vec<(Vec<I>, Vec<I>, f64)>,
+}
+
+/// # how long each stage of a run took
+///
+/// load: reading the transactions, zero when they were already in memory
+///
+/// frequent_sets: finding all FrequentSets of degree 2 and more
+///
+/// rules: generating the association rules
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timings {
+    pub load: Duration,
+    pub frequent_sets: Duration,
+    pub rules: Duration,
+}
+
+/// # non-fatal problems found during a run
+#[derive(Clone, Debug, PartialEq)]
+pub enum Warning {
+    /// more distinct items per transaction than AprioriConfig.max_item_ratio
+    HighItemCardinality {
+        distinct_items: usize,
+        txn_count: usize,
+    },
+    /// no FrequentSet of degree 2 or more, so there can be no rules, lower min_sup to find some
+    NoMultiItemPatterns {
+        min_sup: f64,
+    },
+}
+
+/// # the stages of a run, handed to the callback of AprioriConfig::progress
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressEvent {
+    /// counting the FrequentSets of degree 2 and more starts, a CandicateSet needs min_count,
+    /// total_weight is the support denominator
+    FrequentSetsStarted {
+        min_count: usize,
+        total_weight: f64,
+    },
+    /// the CandicateSets of degree are generated from the frequent FrequentSets one item smaller,
+    /// candidates is None with candidate_batch_size, since the batches are only generated while counting
+    DegreeStarted {
+        degree: usize,
+        frequent_below: usize,
+        candidates: Option<usize>,
+    },
+    /// all CandicateSets of degree are counted, frequent of them became FrequentSets
+    DegreeFinished {
+        degree: usize,
+        candidates: usize,
+        frequent: usize,
+    },
+    /// all FrequentSets are found
+    FrequentSetsFinished {
+        count: usize,
+        duration: Duration,
+    },
+    /// generating the association rules starts
+    RulesStarted {
+        min_conf: f64,
+    },
+    /// a FrequentSet of degree above rule_degree_soft_limit only gets single-item consequents
+    RuleDegreeSoftLimit {
+        degree: usize,
+    },
+    /// the from of a split of a FrequentSet of degree has no support, so its conf would be NaN or infinite,
+    /// the split is skipped, it means the FrequentSets are inconsistent, e.g. fed from outside with a wrong count
+    ZeroAntecedentSupport {
+        degree: usize,
+    },
+    /// the from or to of a split of a FrequentSet of degree is not among the FrequentSets, the split is skipped,
+    /// the FrequentSets lack subsets, e.g. they were filtered or fed from outside
+    MissingSubset {
+        degree: usize,
+    },
+    /// all association rules are generated
+    RulesFinished {
+        count: usize,
+        duration: Duration,
+    },
+    /// the memory held by the FrequentSets and the rules at the end of a run, see MemoryUsage
+    SpaceConsumption {
+        fre_sets_bytes: usize,
+        fre_sets: usize,
+        rules_bytes: usize,
+        rules: usize,
+    },
+}
+
+/// the callback of AprioriConfig::progress, shared by the clones of a config
+#[derive(Clone)]
+struct ProgressCallback(std::sync::Arc<dyn Fn(ProgressEvent) + Send + Sync>);
+
+impl std::fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// the function of AprioriConfig::normalize_items, shared by the clones of a config
+#[derive(Clone)]
+struct ItemNormalizer(std::sync::Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl std::fmt::Debug for ItemNormalizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ItemNormalizer")
+    }
+}
+
+/// # the errors which stop a run
+#[derive(Debug)]
+pub enum AprioriError {
+    /// more distinct items than AprioriConfig.max_distinct_items
+    TooManyDistinctItems {
+        distinct_items: usize,
+        max_distinct_items: usize,
+    },
+    /// a file could not be opened, read or written, e.g. a missing dataset or ignore items file
+    Io(std::io::Error),
+    /// a record of the csv file could not be parsed
+    Csv(csv::Error),
+    /// a threshold out of (0.0, 1.0], name is "min_sup" or "min_conf"
+    InvalidThreshold {
+        name: &'static str,
+        value: f64,
+    },
+    /// min_sup is below 1 / txn_count, so min_count is truncated to 0 and every itemset would be frequent
+    ZeroMinCount {
+        min_sup: f64,
+        txn_count: usize,
+    },
+    /// AprioriConfig::min_support_count was given 0, every itemset would be frequent
+    ZeroMinSupportCount,
+    /// apriori_partitioned split the file into partitions so small that the smallest, of smallest_partition
+    /// transactions, has a local min_count of 0
+    TooManyPartitions {
+        num_partitions: usize,
+        smallest_partition: usize,
+        min_sup: f64,
+    },
+    /// a json file is malformed or of the wrong shape, offset is the byte where reading stopped
+    #[cfg(feature = "serde")]
+    Json {
+        message: String,
+        offset: usize,
+    },
+}
+
+impl std::fmt::Display for AprioriError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AprioriError::TooManyDistinctItems { distinct_items, max_distinct_items } => {
+                write!(f, "{} distinct items, more than the maximum of {}", distinct_items, max_distinct_items)
+            },
+            AprioriError::Io(err) => write!(f, "failed to access the file: {}", err),
+            AprioriError::Csv(err) => write!(f, "failed to parse the csv file: {}", err),
+            AprioriError::InvalidThreshold { name, value } => {
+                write!(f, "{} is {}, it must be in (0.0, 1.0]", name, value)
+            },
+            AprioriError::ZeroMinCount { min_sup, txn_count } => {
+                write!(f, "min_sup {} of {} transactions gives a min_count of 0, every itemset would be frequent", min_sup, txn_count)
+            },
+            AprioriError::ZeroMinSupportCount => f.write_str("min_support_count is 0, every itemset would be frequent"),
+            AprioriError::TooManyPartitions { num_partitions, smallest_partition, min_sup } => {
+                write!(f, "{} partitions leave one of {} transactions, fewer than 1 / min_sup = {}", num_partitions, smallest_partition, 1.0 / min_sup)
+            },
+            #[cfg(feature = "serde")]
+            AprioriError::Json { message, offset } => write!(f, "failed to parse the json file at byte {}: {}", offset, message),
+        }
+    }
+}
+
+impl std::error::Error for AprioriError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AprioriError::Io(err) => Some(err),
+            AprioriError::Csv(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for AprioriError {
+    fn from(err: std::io::Error) -> Self {
+        AprioriError::Io(err)
+    }
+}
+
+/// the csv reader wraps the errors of opening and reading the file, those are Io
+impl From<csv::Error> for AprioriError {
+    fn from(err: csv::Error) -> Self {
+        if err.is_io_error() {
+            match err.into_kind() {
+                csv::ErrorKind::Io(err) => AprioriError::Io(err),
+                _ => unreachable!("is_io_error means ErrorKind::Io"),
+            }
+        } else {
+            AprioriError::Csv(err)
+        }
+    }
+}
+
+/// # the support threshold of one run
+///
+/// min_count: the threshold on count, (txn_count * min_sup) truncated, or given by AprioriConfig::min_support_count
+///
+/// min_weight: the threshold on weighted_count, only used under time decay
+///
+/// total_weight: the sum of all transaction weights, equal to txn_count without decay
+///
+/// txn_count is the number of original transactions, the sum of Txn.weight
+struct MinSupport {
+    min_count: usize,
+    min_weight: Option<f64>,
+    total_weight: f64,
+}
+
+impl MinSupport {
+    fn new(min_sup: f64, txn_count: usize, weights: &[f64], decayed: bool) -> MinSupport {
+        let total_weight: f64 = weights.iter().sum();
+
+        MinSupport {
+            min_count: (txn_count as f64 * min_sup) as usize,
+            min_weight: if decayed { Some(total_weight * min_sup) } else { None },
+            total_weight,
+        }
+    }
+
+    /// the threshold of AprioriConfig::min_support_count, min_count as it is
+    fn from_count(min_count: usize, txn_count: usize, weights: &[f64], decayed: bool) -> MinSupport {
+        let total_weight: f64 = weights.iter().sum();
+
+        MinSupport {
+            min_count,
+            min_weight: if decayed { Some(total_weight * min_count as f64 / txn_count as f64) } else { None },
+            total_weight,
+        }
+    }
+
+    /// ## fail when min_count is 0, unless there is no transaction at all
+    ///
+    /// under time decay the threshold is min_weight, which is above 0 for any valid min_sup
+    fn check_nonzero(&self, min_sup: f64, txn_count: usize) -> Result<(), AprioriError> {
+        if self.min_weight.is_none() && self.min_count == 0 && txn_count > 0 {
+            return Err(AprioriError::ZeroMinCount { min_sup, txn_count });
+        }
+
+        Ok(())
+    }
+
+    /// ## the fraction of transactions min_count stands for
+    ///
+    /// txn_count * min_sup is truncated to get min_count, so the threshold applied is min_count / txn_count,
+    /// slightly below min_sup, e.g. 49 / 9835 = 0.00498 for 0.005
+    ///
+    /// under time decay the threshold is on the weights and not truncated, so it is min_sup itself
+    fn effective(&self, txn_count: usize) -> f64 {
+        match self.min_weight {
+            Some(min_weight) => min_weight / self.total_weight,
+            None => self.min_count as f64 / txn_count as f64,
+        }
+    }
+
+    /// whether a CandicateSet of any degree with these counts is frequent, reaching the threshold is enough
+    fn is_frequent(&self, count: usize, weighted_count: f64) -> bool {
+        match self.min_weight {
+            Some(min_weight) => weighted_count >= min_weight,
+            None => count >= self.min_count,
+        }
+    }
+}
+
+/// the ultimate interface to call apriori function
+/// 
+/// arg
+/// min_sup: minimum support
+/// min_conf: minimum confidence
+/// filename: the dataset's filename, only for csv file now
+/// 
+/// return 
+/// fre_sets: all frequentSet, sorted by degree then items
+/// association_rule_set: all association rules
+///
+/// or AprioriError when the file can't be read or parsed, a threshold is out of (0.0, 1.0]
+/// or min_sup is so small that min_count is 0
+pub fn apriori(min_sup: f64, min_conf: f64, filename: &str) -> Result<(Vec<FrequentSet>, Vec<AssociationRule>), AprioriError> {
+    let transactions = read_txns(filename, &AprioriConfig::new())?.map(|txn| txn.map(|x| x.items)).collect::<Result<Vec<_>, _>>()?;
+
+    apriori_from_transactions(min_sup, min_conf, transactions)
+}
+
+/// ## apriori, and the AprioriStats of the run
+///
+/// the same FrequentSets and rules as apriori, the stats come from its AprioriResult
+pub fn apriori_with_stats(min_sup: f64, min_conf: f64, filename: &str) -> Result<(Vec<FrequentSet>, Vec<AssociationRule>, AprioriStats), AprioriError> {
+    let result = AprioriConfig::new()
+        .min_support(min_sup)
+        .min_confidence(min_conf)
+        .run_on_file(filename)?;
+    let stats = result.stats();
+
+    Ok((result.fre_sets, result.association_rules_set, stats))
+}
+
+/// ## apriori on transactions already in memory, one Vec of items per transaction
+///
+/// the same as apriori without the csv file, the items of each transaction are sorted as when read from a file,
+/// empty items are kept, filter them out beforehand if they stand for missing values
+pub fn apriori_from_transactions(min_sup: f64, min_conf: f64, transactions: Vec<Vec<String>>) -> Result<(Vec<FrequentSet>, Vec<AssociationRule>), AprioriError> {
+    let result = AprioriConfig::new()
+        .min_support(min_sup)
+        .min_confidence(min_conf)
+        .run_on_transactions(transactions)?;
+
+    Ok((result.fre_sets, result.association_rules_set))
+}
+
+/// ## apriori on a csv file too large for memory, with the Partition algorithm
+///
+/// the file is read in num_partitions consecutive partitions, each one is mined alone at the same min_sup,
+/// then the FrequentSets of all partitions are counted together in a second pass over the file
+///
+/// an itemset frequent in the whole file is frequent in at least one partition, so the union of the local
+/// FrequentSets holds all of them, the ones reaching min_sup globally are the same as apriori on the whole file
+///
+/// only one partition and the candidates are in memory at a time, the file is read three times:
+/// to count the transactions, to mine the partitions and to count the candidates
+///
+/// every partition needs at least 1 / min_sup transactions, so its local min_count is 1 or more,
+/// AprioriError::TooManyPartitions otherwise, a min_count of 0 would make every candidate locally frequent
+pub fn apriori_partitioned(min_sup: f64, min_conf: f64, filename: &str, num_partitions: usize) -> Result<(Vec<FrequentSet>, Vec<AssociationRule>), AprioriError> {
+
+    let config = AprioriConfig::new().min_support(min_sup).min_confidence(min_conf);
+    config.check_thresholds()?;
+
+    let txn_num = read_txns(filename, &config)?.try_fold(0_usize, |num, txn| txn.map(|_| num + 1))?;
+    let partition_size = txn_num.div_ceil(num_partitions.max(1)).max(1);
+
+    let global_min_sup = MinSupport::new(min_sup, txn_num, &vec![1.0; txn_num], false);
+    global_min_sup.check_nonzero(min_sup, txn_num)?;
+
+    // the last partition holds the remainder, it is the smallest
+    let partition_num = txn_num.div_ceil(partition_size);
+    let smallest_partition = txn_num - partition_size * partition_num.saturating_sub(1);
+    if partition_num > 0 && ((smallest_partition as f64 * min_sup) as usize) == 0 {
+        return Err(AprioriError::TooManyPartitions { num_partitions, smallest_partition, min_sup });
+    }
+
+    // phase 1: the local FrequentSets of every partition
+    let mut local_fre_items: CountSet<Vec<String>> = CountSet::default();
+    let mut partition: Vec<Txn> = Vec::with_capacity(partition_size);
+    let mut txns = read_txns(filename, &config)?.peekable();
+
+    while txns.peek().is_some() {
+        for txn in txns.by_ref().take(partition_size) {
+            partition.push(txn?);
+        }
+
+        let weights = txn_weights(&partition, None);
+        let local_min_sup = MinSupport::new(min_sup, txn_count(&partition), &weights, false);
+        let mut local_fre_sets: Vec<FrequentSet> = Vec::new();
+
+        init_fre_set(&mut partition, &weights, &local_min_sup, None, &mut local_fre_sets).expect("no limit on distinct items");
+        generate_all_fre_sets(&mut local_fre_sets, &partition, &weights, &local_min_sup, &AprioriConfig::new(), &mut None, &mut Vec::new());
+
+        local_fre_items.extend(local_fre_sets.into_iter().map(|x| x.items));
+        partition.clear();
+    }
+
+    // phase 2: the global count of every local FrequentSet
+    let mut candi_sets: Vec<CandicateSet> = local_fre_items.into_iter()
+        .map(|items| CandicateSet { degree: items.len(), items, count: 0, weighted_count: 0.0 })
+        .collect();
+
+    for txn in read_txns(filename, &config)? {
+        let txn = txn?;
+        for candi_set in candi_sets.iter_mut() {
+            if subset_of(&candi_set.items, &txn.items) {
+                candi_set.count += txn.weight;
+            }
+        }
+    }
+
+    let mut fre_sets: Vec<FrequentSet> = candi_sets.into_iter()
+        .filter(|x| global_min_sup.is_frequent(x.count, 0.0))
+        .map(|x| FrequentSet { degree: x.degree, items: x.items, count: x.count, weighted_count: x.count as f64, tids: Vec::new() })
+        .collect();
+
+    // the same order as mining the whole file
+    sort_fre_sets(&mut fre_sets);
+
+    let mut association_rules_set: Vec<AssociationRule> = Vec::new();
+    generate_association_rules(&fre_sets, &AprioriConfig::new().min_confidence(min_conf), &mut association_rules_set, txn_num as f64);
+
+    Ok((fre_sets, association_rules_set))
+}
+
+/// ## the FrequentSets of txn_set with Eclat, intersecting tidsets instead of rescanning the transactions
+///
+/// every item gets the tidset of the txns containing it in one scan, then the count of an itemset is the weight
+/// of the intersection of the tidsets of the two itemsets it is joined from, the txns are never read again
+///
+/// return the same FrequentSets as apriori at min_sup without time decay, in the same order, by degree then items,
+/// the rules come from them like from any FrequentSets, e.g. with top_rules or rules_for_confidences
+///
+/// faster than counting on dense data, at the cost of holding the tidsets of a whole branch in memory
+pub fn eclat<I: Item>(min_sup: f64, txn_set: &[Txn<I>]) -> Result<Vec<FrequentSet<I>>, AprioriError> {
+
+    AprioriConfig::<I>::default().min_support(min_sup).check_thresholds()?;
+
+    let txn_num = txn_count(txn_set);
+    let threshold = MinSupport::new(min_sup, txn_num, &txn_weights(txn_set, None), false);
+    threshold.check_nonzero(min_sup, txn_num)?;
+
+    // the tidset of every item, the positions of the txns containing it, ascending
+    let mut item_tids: BTreeMap<&I, Vec<usize>> = BTreeMap::new();
+    for (pos, txn) in txn_set.iter().enumerate() {
+        for item in txn.items.iter() {
+            let tids = item_tids.entry(item).or_default();
+            if tids.last() != Some(&pos) {
+                tids.push(pos);
+            }
+        }
+    }
+
+    // the frequent items in lexicographic order, all sharing the empty prefix
+    let class: Vec<EclatNode<I>> = item_tids.into_iter()
+        .map(|(item, tids)| EclatNode { items: vec![item.clone()], count: tidset_weight(&tids, txn_set), tids })
+        .filter(|x| threshold.is_frequent(x.count, 0.0))
+        .collect();
+
+    let mut fre_sets: Vec<FrequentSet<I>> = Vec::new();
+    eclat_class(&class, txn_set, &threshold, &mut fre_sets);
+
+    // the same order as apriori
+    sort_fre_sets(&mut fre_sets);
+
+    Ok(fre_sets)
+}
+
+/// ## sort FrequentSets by degree, then lexicographically by items
+///
+/// the order of AprioriResult.fre_sets, apriori_partitioned and eclat, guaranteed so that the output of a run
+/// can be compared to a golden file, whatever order the candidates happen to be generated in
+///
+/// no two FrequentSets have the same items, so the order is total and does not depend on the input order
+fn sort_fre_sets<I: Item>(fre_sets: &mut [FrequentSet<I>]) {
+    fre_sets.sort_unstable_by(|a, b| a.degree.cmp(&b.degree).then_with(|| a.items.cmp(&b.items)));
+}
+
+/// ## build transactions from rows of nullable columns
+///
+/// each row is one transaction and each column value one item, None and empty values are absent items,
+/// the shape columnar readers like parquet or arrow give, feed the result to AprioriConfig::run_on_txn_set
+///
+/// a row of only absent items is still kept as an empty Txn, it counts as a transaction but holds no item
+pub fn txns_from_nullable_rows<I: IntoIterator<Item = Vec<Option<String>>>>(rows: I) -> Vec<Txn> {
+    rows.into_iter().enumerate().map(|(i, row)| {
+        Txn::new(i, row.into_iter().flatten().filter(|x| !x.is_empty()).collect())
+    }).collect()
+}
+
+/// ## build transactions from (transaction_id, item) pairs
+///
+/// the long shape of a database table with one row per item, e.g. `SELECT basket_id, item FROM purchases`,
+/// the pairs of a transaction do not need to be next to each other
+///
+/// transactions are in the order their id first appears, None and empty items are absent items,
+/// a transaction whose items are all absent is still kept, empty, and a repeated pair counts once
+pub fn txns_from_pairs<K, I>(pairs: I) -> Vec<Txn>
+where
+    K: Eq + std::hash::Hash,
+    I: IntoIterator<Item = (K, Option<String>)>,
+{
+    let mut positions: HashMap<K, usize> = HashMap::new();
+    let mut baskets: Vec<Vec<String>> = Vec::new();
+
+    for (txn_id, item) in pairs {
+        let pos = *positions.entry(txn_id).or_insert_with(|| {
+            baskets.push(Vec::new());
+            baskets.len() - 1
+        });
+
+        if let Some(item) = item.filter(|x| !x.is_empty()) {
+            baskets[pos].push(item);
+        }
+    }
+
+    baskets.into_iter().enumerate().map(|(i, mut items)| {
+        items.sort();
+        items.dedup();
+        Txn::new(i, items)
+    }).collect()
+}
+
+/// ## read a file of one transaction per line, its items split by separator
+///
+/// the format of the FIMI benchmark files like retail, mushroom and chess, with ' ' as separator,
+/// every item is trimmed and empty items are dropped, so repeated separators are fine, blank lines are skipped
+///
+/// the items of each Txn are sorted, its id is the number of the transaction, counting from 0
+pub fn create_txn_set_from_lines(filename: &str, separator: char) -> Result<Vec<Txn>, AprioriError> {
+    use std::io::BufRead;
+
+    let reader = std::io::BufReader::new(std::fs::File::open(filename)?);
+    let mut txn_set: Vec<Txn> = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+
+        let items: Vec<String> = line.split(separator).map(|x| x.trim()).filter(|x| !x.is_empty()).map(|x| x.to_string()).collect();
+        if items.is_empty() {
+            continue;
+        }
+
+        txn_set.push(Txn::new(txn_set.len(), items));
+    }
+
+    Ok(txn_set)
+}
+
+/// ## write all association rules to file
+///
+/// the same text as write_rules, failing when the file can't be created or written
+pub fn write_rules_to_file(filename: &str, association_rules_set: &[AssociationRule]) -> std::io::Result<()> {
+
+    let mut file = std::io::BufWriter::new(std::fs::File::create(filename)?);
+
+    write_rules(&mut file, association_rules_set)?;
+    file.flush()
+}
+
+/// ## write all association rules to writer, e.g. stdout or a Vec<u8>
+///
+/// every rule is numbered and its from and to pretty-printed, the format of write_rules_to_file
+pub fn write_rules<W: Write>(writer: &mut W, association_rules_set: &[AssociationRule]) -> std::io::Result<()> {
+
+    for (i, rule) in association_rules_set.iter().enumerate() {
+        writer.write_fmt(format_args!("\nrule {}:\n{:#?} --> {:#?}, sup = {}, conf = {}\n",
+            i, rule.from, rule.to, rule.sup, rule.conf))?;
+    }
+
+    Ok(())
+}
+
+/// ## write the FrequentSets to a long-format csv, one row per item of each set
+///
+/// columns: itemset_id, item, degree, support, with support = count / txn_num,
+/// easy to pivot or group by itemset_id in pandas
+///
+/// fails when the file can't be created or written
+pub fn write_fre_sets_long_csv(filename: &str, fre_sets: &[FrequentSet], txn_num: usize) -> Result<(), AprioriError> {
+
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["itemset_id", "item", "degree", "support"])?;
+
+    for (i, fre_set) in fre_sets.iter().enumerate() {
+        let support = (fre_set.count as f64 / txn_num as f64).to_string();
+
+        for item in fre_set.items.iter() {
+            writer.write_record([&i.to_string(), item, &fre_set.degree.to_string(), &support])?;
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// ## write the rules to a long-format csv, one row per item of each side of each rule
+///
+/// columns: rule_id, side, item, support, confidence, lift, where side is "from" or "to"
+///
+/// fails when the file can't be created or written
+pub fn write_rules_long_csv(filename: &str, association_rules_set: &[AssociationRule]) -> Result<(), AprioriError> {
+
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["rule_id", "side", "item", "support", "confidence", "lift"])?;
+
+    for (i, rule) in association_rules_set.iter().enumerate() {
+        let metrics = [rule.sup.to_string(), rule.conf.to_string(), rule.lift.to_string()];
+
+        for (side, items) in [("from", &rule.from), ("to", &rule.to)] {
+            for item in items.iter() {
+                writer.write_record([&i.to_string(), side, item, &metrics[0], &metrics[1], &metrics[2]])?;
+            }
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// ## write the rules to a csv, one row per rule
+///
+/// columns: antecedent, consequent, support, confidence, lift, the items of each side joined by "|",
+/// ready for a spreadsheet or pandas.read_csv
+pub fn write_rules_to_csv(filename: &str, association_rules_set: &[AssociationRule]) -> Result<(), AprioriError> {
+
+    let mut writer = csv::Writer::from_path(filename)?;
+    writer.write_record(["antecedent", "consequent", "support", "confidence", "lift"])?;
+
+    for rule in association_rules_set.iter() {
+        writer.write_record([rule.from.join("|"), rule.to.join("|"), rule.sup.to_string(), rule.conf.to_string(), rule.lift.to_string()])?;
+    }
+
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// ## a sentence explaining the rule to someone who does not know what support or lift is
+///
+/// e.g. "Customers who buy bread and butter are 1.8× more likely to also buy milk (45% of the time, based on 1.2% of baskets)."
+///
+/// the factor is the lift, the first percentage the confidence and the second the support
+pub fn explain_rule(rule: &AssociationRule) -> String {
+
+    let likelihood = if rule.lift >= 1.0 {
+        format!("{:.1}× more likely", rule.lift)
+    } else {
+        format!("only {:.1}× as likely", rule.lift)
+    };
+
+    format!("Customers who buy {} are {} to also buy {} ({:.0}% of the time, based on {:.1}% of baskets).",
+        join_items(&rule.from), likelihood, join_items(&rule.to), rule.conf * 100.0, rule.sup * 100.0)
+}
+
+/// "a", "a and b", "a, b and c"
+fn join_items(items: &[String]) -> String {
+    match items.split_last() {
+        None => String::new(),
+        Some((last, [])) => last.clone(),
+        Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+    }
+}
+
+/// ## the count of every item in txn_set, frequent or not
+///
+/// sorted by count descending, items with the same count in lexicographic order
+pub fn item_counts(txn_set: &[Txn]) -> Vec<(String, usize)> {
+
+    let candicate_set_1 = create_candicate_set_1(txn_set, &txn_weights(txn_set, None));
+
+    let mut counts: Vec<(String, usize)> = candicate_set_1.into_iter().map(|x| (x.0, x.1.0)).collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    counts
+}
+
+/// ## the support of items looked up in fre_sets, count / txn_num
+///
+/// items can be in any order, they are sorted like the items of the FrequentSets first,
+/// None when the itemset is not frequent, or txn_num is 0, exact_support counts it whatever its support
+pub fn support_of<I: Item>(fre_sets: &[FrequentSet<I>], items: &[I], txn_num: usize) -> Option<f64> {
+
+    if txn_num == 0 {
+        return None;
+    }
+
+    let mut items = items.to_vec();
+    items.sort();
+    items.dedup();
+
+    fre_sets.iter().find(|x| x.items == items).map(|x| x.support(txn_num))
+}
+
+/// ## the support of any itemset, counted by scanning txn_set
+///
+/// unlike the FrequentSets, this works whether the itemset is frequent or not,
+/// None when txn_set is empty and support is undefined
+pub fn exact_support(items: &[String], txn_set: &[Txn]) -> Option<f64> {
+
+    if txn_set.is_empty() {
+        return None;
+    }
+
+    // sorted like the items of the txns
+    let mut items = items.to_vec();
+    items.sort();
+    items.dedup();
+
+    Some(count_containing(&items, txn_set) as f64 / txn_count(txn_set) as f64)
+}
+
+/// ## the highest min_sup at which items is frequent
+///
+/// that is its exact support, the breakeven point: at any lower min_sup the itemset is mined,
+/// above it it is not, 0.0 when txn_set is empty or nothing contains the itemset
+pub fn threshold_for_itemset(items: &[String], txn_set: &[Txn]) -> f64 {
+    exact_support(items, txn_set).unwrap_or(0.0)
+}
+
+/// ## the exact support of many itemsets in a single scan of txn_set
+///
+/// same as calling exact_support on each query, but every txn is visited only once
+pub fn batch_support(queries: &[Vec<String>], txn_set: &[Txn]) -> Vec<Option<f64>> {
+
+    if txn_set.is_empty() {
+        return vec![None; queries.len()];
+    }
+
+    let mut counts = vec![0; queries.len()];
+
+    // sorted like the items of the txns
+    let queries: Vec<Vec<String>> = queries.iter().map(|x| {
+        let mut items = x.clone();
+        items.sort();
+        items.dedup();
+        items
+    }).collect();
+
+    for txn in txn_set.iter() {
+        for (query, count) in queries.iter().zip(counts.iter_mut()) {
+            if subset_of(query, &txn.items) {
+                *count += txn.weight;
+            }
+        }
+    }
+
+    let txn_num = txn_count(txn_set);
+
+    counts.into_iter().map(|x| Some(x as f64 / txn_num as f64)).collect()
+}
+
+/// # how much each metric counts in rule_score
+///
+/// by default support: 0.5, confidence: 1.0, lift: 1.0, support is weighted less
+/// since it is small for most rules and would otherwise drown the other two
+#[derive(Clone, Copy, Debug)]
+pub struct ScoreWeights {
+    pub support: f64,
+    pub confidence: f64,
+    pub lift: f64,
+}
+
+impl Default for ScoreWeights {
+    fn default() -> Self {
+        ScoreWeights {
+            support: 0.5,
+            confidence: 1.0,
+            lift: 1.0,
+        }
+    }
+}
+
+/// ## one number to rank rules by, combining support, confidence and lift
+///
+/// score = w.support * ln(sup) + w.confidence * ln(conf) + w.lift * ln(lift)
+///
+/// i.e. the log of sup^w.support * conf^w.confidence * lift^w.lift, the higher the better,
+/// a weight of 0 ignores that metric, and a rule with lift below 1 is penalized as long as w.lift > 0
+///
+/// only the order of the scores means something, compare them between rules of the same dataset
+pub fn rule_score(rule: &AssociationRule, weights: ScoreWeights) -> f64 {
+    weights.support * rule.sup.ln() + weights.confidence * rule.conf.ln() + weights.lift * rule.lift.ln()
+}
+
+/// # an f64 which can be a HashMap key, ordered and compared by f64::total_cmp
+#[derive(Clone, Copy, Debug)]
+pub struct OrderedFloat(pub f64);
+
+impl PartialEq for OrderedFloat {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(&other.0).is_eq()
+    }
+}
+
+impl Eq for OrderedFloat {}
+
+impl PartialOrd for OrderedFloat {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OrderedFloat {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl std::hash::Hash for OrderedFloat {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+/// ## the association rules of fre_sets at several min_conf at once
+///
+/// FrequentSets do not depend on min_conf, so mine them once and sweep the confidences here:
+/// the rules are generated a single time at the lowest confidence, then each confidence keeps
+/// the ones reaching it, the same rules as a full run per confidence
+///
+/// fre_sets are from a run without time decay over txn_num transactions
+pub fn rules_for_confidences(fre_sets: &[FrequentSet], confidences: &[f64], txn_num: usize) -> HashMap<OrderedFloat, Vec<AssociationRule>> {
+
+    let mut rules_by_conf: HashMap<OrderedFloat, Vec<AssociationRule>> = HashMap::new();
+
+    let lowest = match confidences.iter().copied().min_by(f64::total_cmp) {
+        Some(lowest) => lowest,
+        None => return rules_by_conf,
+    };
+
+    let mut rules: Vec<AssociationRule> = Vec::new();
+    generate_association_rules(fre_sets, &AprioriConfig::new().min_confidence(lowest), &mut rules, txn_num as f64);
+
+    for &min_conf in confidences.iter() {
+        let kept: Vec<AssociationRule> = rules.iter().filter(|x| x.conf >= min_conf).cloned().collect();
+        rules_by_conf.insert(OrderedFloat(min_conf), kept);
+    }
+
+    rules_by_conf
+}
+
+/// ## rules ranked by lift, with popular consequents penalized
+///
+/// surprise = lift * (1 - sup(to)), highest first, so rules towards rare items rise above
+/// the ones every basket leads to anyway, like whole milk
+///
+/// sup(to) is taken from fre_sets, mined over txn_num transactions
+pub fn surprise_rank<'a>(rules: &'a [AssociationRule], fre_sets: &[FrequentSet], txn_num: usize) -> Vec<&'a AssociationRule> {
+
+    let index = support_index(fre_sets);
+
+    let mut ranked: Vec<(&AssociationRule, f64)> = rules.iter().map(|rule| {
+        let to_sup = match index.get(rule.to.as_slice()) {
+            Some(to_fre_set) => to_fre_set.count as f64 / txn_num as f64,
+            // not mined, but lift = conf / sup(to)
+            None => rule.conf / rule.lift,
+        };
+        (rule, rule.lift * (1.0 - to_sup))
+    }).collect();
+
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    ranked.into_iter().map(|x| x.0).collect()
+}
+
+/// ## send the association rules of fre_sets into a channel as they are generated
+///
+/// with a bounded channel from std::sync::mpsc::sync_channel, generation waits while the channel is full,
+/// so a slow consumer on another thread holds back the producer instead of rules piling up in memory
+///
+/// the rules are the same as a run at min_conf would give, fre_sets being mined without time decay
+/// over txn_num transactions, but in the order they are found, sorting would need them all first
+///
+/// sender is dropped when all rules are sent, which ends the consumer's iteration over the receiver,
+/// generation stops as soon as a send finds the receiver gone, the remaining rules are never generated
+pub fn generate_rules_to_channel(fre_sets: &[FrequentSet], min_conf: f64, txn_num: usize, sender: std::sync::mpsc::SyncSender<AssociationRule>) {
+
+    for_each_rule(fre_sets, fre_sets, &AprioriConfig::new().min_confidence(min_conf), txn_num as f64, &mut |rule| {
+        match sender.send(rule) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => ControlFlow::Break(()),
+        }
+    });
+}
+
+/// ## the association rules of hand-picked itemsets, counted directly over txn_set
+///
+/// no mining: the support of each itemset and of all its subsets is counted with one scan of txn_set,
+/// like exact_support, then every split with conf >= min_conf gives a rule, as generate_association_rules does
+///
+/// an itemset of k items needs 2^k - 1 counts, meant for a few itemsets to check, not a whole lattice
+pub fn rules_from_itemsets(itemsets: &[Vec<String>], txn_set: &[Txn], min_conf: f64) -> Vec<AssociationRule> {
+
+    // every itemset sorted, then all the non-empty subsets
+    let mut sources: Vec<Vec<String>> = itemsets.iter().map(|x| {
+        let mut items = x.clone();
+        items.sort();
+        items.dedup();
+        items
+    }).collect();
+
+    // an itemset given twice gives its rules once
+    let mut seen: CountSet<Vec<String>> = CountSet::default();
+    sources.retain(|x| !x.is_empty() && seen.insert(x.clone()));
+
+    let mut lattice: CountSet<Vec<String>> = CountSet::default();
+    for items in sources.iter() {
+        lattice.insert(items.clone());
+
+        // the bitmap is one bool per item, so no itemset overflows an integer mask
+        let mut bitmap = vec![false; items.len()];
+        while next_bitmap(&mut bitmap) {
+            lattice.insert(items.iter().zip(&bitmap).filter(|x| *x.1).map(|x| x.0.clone()).collect());
+        }
+    }
+
+    let mut counts: Vec<(Vec<String>, usize)> = lattice.into_iter().map(|x| (x, 0)).collect();
+    for txn in txn_set.iter() {
+        for (items, count) in counts.iter_mut() {
+            if subset_of(items, &txn.items) {
+                *count += txn.weight;
+            }
+        }
+    }
+
+    let fre_sets: Vec<FrequentSet> = counts.into_iter()
+        .map(|(items, count)| FrequentSet { degree: items.len(), items, count, weighted_count: count as f64, tids: Vec::new() })
+        .collect();
+
+    let index = support_index(&fre_sets);
+    let source_sets: Vec<&FrequentSet> = sources.iter().map(|x| index[x.as_slice()]).collect();
+
+    let mut rules: Vec<AssociationRule> = Vec::new();
+    for_each_rule(source_sets, &fre_sets, &AprioriConfig::new().min_confidence(min_conf), txn_count(txn_set) as f64, &mut |rule| {
+        rules.push(rule);
+        ControlFlow::Continue(())
+    });
+
+    rules
+}
+
+/// ## the mean and standard deviation of a rule's lift over random subsamples of txn_set
+///
+/// each of the samples draws sample_frac of the Txns without replacement and recomputes
+/// lift = n * count(from ∪ to) / (count(from) * count(to)) on them, a large deviation means the lift is a fluke
+///
+/// the draws only depend on seed, the same seed gives the same result, samples where from or to
+/// never occurs have no lift and are left out, (NaN, NaN) when no sample is left
+pub fn lift_stability(rule: &AssociationRule, txn_set: &[Txn], samples: usize, sample_frac: f64, seed: u64) -> (f64, f64) {
+
+    let mut rng = SplitMix64(seed);
+    let sample_size = ((txn_set.len() as f64 * sample_frac).round() as usize).min(txn_set.len());
+
+    let mut order: Vec<usize> = (0..txn_set.len()).collect();
+    let mut lifts: Vec<f64> = Vec::with_capacity(samples);
+
+    for _ in 0..samples {
+        // partial Fisher-Yates, the first sample_size positions are the sample
+        for i in 0..sample_size {
+            let j = i + rng.below(order.len() - i);
+            order.swap(i, j);
+        }
+
+        let (mut n, mut from_count, mut to_count, mut both_count) = (0, 0, 0, 0);
+        for txn in order[..sample_size].iter().map(|&i| &txn_set[i]) {
+            let from_present = subset_of(&rule.from, &txn.items);
+            let to_present = subset_of(&rule.to, &txn.items);
+
+            n += txn.weight;
+            if from_present {
+                from_count += txn.weight;
+            }
+            if to_present {
+                to_count += txn.weight;
+            }
+            if from_present && to_present {
+                both_count += txn.weight;
+            }
+        }
+
+        if from_count > 0 && to_count > 0 {
+            lifts.push(n as f64 * both_count as f64 / (from_count as f64 * to_count as f64));
+        }
+    }
+
+    if lifts.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+
+    let mean = lifts.iter().sum::<f64>() / lifts.len() as f64;
+    let variance = lifts.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / lifts.len() as f64;
+
+    (mean, variance.sqrt())
+}
+
+/// ## sort rules by confidence descending, then support descending
+///
+/// the remaining ties are broken by from and then to, so the order does not depend on how the rules were found,
+/// every run sorts its rules this way
+pub fn sort_rules_by_confidence<I: Item>(rules: &mut [AssociationRule<I>]) {
+    rules.sort_by(confidence_order);
+}
+
+/// the order of sort_rules_by_confidence, Less when a comes first
+fn confidence_order<I: Item>(a: &AssociationRule<I>, b: &AssociationRule<I>) -> std::cmp::Ordering {
+    b.conf.total_cmp(&a.conf)
+        .then_with(|| b.sup.total_cmp(&a.sup))
+        .then_with(|| a.from.cmp(&b.from))
+        .then_with(|| a.to.cmp(&b.to))
+}
+
+/// ## the n best rules of fre_sets by confidence, without keeping all the others
+///
+/// the rules are generated one by one at min_conf into a heap bounded to n rules, whose top is the worst kept,
+/// so at most n + 1 rules are in memory at a time, the same as AprioriConfig::max_rules
+///
+/// return the same rules as the first n of a run at min_conf, sorted by sort_rules_by_confidence,
+/// fre_sets being mined without time decay over txn_num transactions
+pub fn top_rules<I: Item>(fre_sets: &[FrequentSet<I>], min_conf: f64, txn_num: usize, n: usize) -> Vec<AssociationRule<I>> {
+
+    let config = AprioriConfig::default().min_confidence(min_conf);
+    let mut rules = collect_rules(Some(n), |emit| for_each_rule(fre_sets, fre_sets, &config, txn_num as f64, emit));
+    sort_rules_by_confidence(&mut rules);
+
+    rules
+}
+
+/// an AssociationRule ordered by confidence_order, the greatest is the one sorted last
+struct RankedRule<I>(AssociationRule<I>);
+
+impl<I: Item> PartialEq for RankedRule<I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == std::cmp::Ordering::Equal
+    }
+}
+
+impl<I: Item> Eq for RankedRule<I> {}
+
+impl<I: Item> PartialOrd for RankedRule<I> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<I: Item> Ord for RankedRule<I> {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        confidence_order(&self.0, &other.0)
+    }
+}
+
+/// ## the rules which fire on basket, those whose from is in it
+///
+/// the basket can be in any order, the rules are sorted by sort_rules_by_confidence,
+/// the to of a rule may already be in the basket too, see RecommendationIndex for the items to suggest
+pub fn predict<'a, I: Item>(rules: &'a [AssociationRule<I>], basket: &[I]) -> Vec<&'a AssociationRule<I>> {
+
+    // sorted like the from of the rules
+    let mut basket = basket.to_vec();
+    basket.sort();
+    basket.dedup();
+
+    let mut fired: Vec<&AssociationRule<I>> = rules.iter().filter(|x| subset_of(&x.from, &basket)).collect();
+    fired.sort_by(|a, b| confidence_order(a, b));
+
+    fired
+}
+
+/// ## keep the rules with lift >= min_lift, in their order
+///
+/// a lift near 1.0 means from and to are independent, a min_lift a bit above it drops the rules
+/// which only have a high conf because to is common, like everyone buying bread
+pub fn filter_rules_by_lift<I: Item>(rules: Vec<AssociationRule<I>>, min_lift: f64) -> Vec<AssociationRule<I>> {
+    rules.into_iter().filter(|x| x.lift >= min_lift).collect()
+}
+
+/// ## bucket rules into confidence bands
+///
+/// bands are the lower bounds of the tiers, from the highest, e.g. with &[0.9, 0.7]:
+///
+/// tier 0: conf >= 0.9, tier 1: 0.7 <= conf < 0.9, tier 2: conf < 0.7
+///
+/// i.e. a lower bound is inclusive and the bound above exclusive, bands given in another order are sorted first,
+/// only the tiers with rules are in the map, and the rules keep their order within a tier
+pub fn tier_rules(rules: Vec<AssociationRule>, bands: &[f64]) -> BTreeMap<usize, Vec<AssociationRule>> {
+
+    let mut bands = bands.to_vec();
+    bands.sort_by(|a, b| b.total_cmp(a));
+
+    let mut tiers: BTreeMap<usize, Vec<AssociationRule>> = BTreeMap::new();
+
+    for rule in rules {
+        let tier = bands.iter().position(|&x| rule.conf >= x).unwrap_or(bands.len());
+        tiers.entry(tier).or_default().push(rule);
+    }
+
+    tiers
+}
+
+/// ## the overlap between the transactions two rules fire on
+///
+/// a rule fires on a txn when both its from and its to are in the txn
+///
+/// return the Jaccard overlap |A ∩ B| / |A ∪ B| of the two sets of txns, 0 when neither rule fires at all
+pub fn rule_support_overlap(a: &AssociationRule, b: &AssociationRule, txn_set: &[Txn]) -> f64 {
+
+    let mut both = 0;
+    let mut either = 0;
+
+    for txn in txn_set.iter() {
+        let a_fires = subset_of(&a.from, &txn.items) && subset_of(&a.to, &txn.items);
+        let b_fires = subset_of(&b.from, &txn.items) && subset_of(&b.to, &txn.items);
+
+        if a_fires && b_fires {
+            both += txn.weight;
+        }
+        if a_fires || b_fires {
+            either += txn.weight;
+        }
+    }
+
+    if either == 0 {
+        return 0.0;
+    }
+
+    both as f64 / either as f64
+}
+
+/// ## the contingency table of a rule over txn_set
+///
+/// return [n11, n10, n01, n00], the number of txns where
+///
+/// n11: from present, to present
+///
+/// n10: from present, to absent
+///
+/// n01: from absent, to present
+///
+/// n00: from absent, to absent
+pub fn contingency_table(rule: &AssociationRule, txn_set: &[Txn]) -> [usize; 4] {
+
+    let mut table = [0; 4];
+
+    for txn in txn_set.iter() {
+        let from_present = subset_of(&rule.from, &txn.items);
+        let to_present = subset_of(&rule.to, &txn.items);
+
+        let cell = match (from_present, to_present) {
+            (true, true) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (false, false) => 3,
+        };
+
+        table[cell] += txn.weight;
+    }
+
+    table
+}
+
+/// # association rules grouped by antecedent, ready to serve recommendations
+///
+/// rules: each from mapped to the (to, conf, lift, sup) of its rules, sorted by conf descending
+#[derive(Debug, Default)]
+pub struct RecommendationIndex {
+    rules: HashMap<Vec<String>, Vec<Consequent>>,
+}
+
+/// the (to, conf, lift, sup) of an indexed rule
+type Consequent = (Vec<String>, f64, f64, f64);
+
+/// # how RecommendationIndex::recommend_with ranks and cuts the recommendations
+///
+/// top_n: at most this many items, None for all, by default None
+///
+/// min_confidence: only items recommended by a rule with at least this conf, by default 0.0
+///
+/// tie_break: how items with the same best conf are ordered, by default TieBreak::LiftThenSupport
+#[derive(Clone, Copy, Debug)]
+pub struct RecommendOptions {
+    pub top_n: Option<usize>,
+    pub min_confidence: f64,
+    pub tie_break: TieBreak,
+}
+
+impl Default for RecommendOptions {
+    fn default() -> Self {
+        RecommendOptions {
+            top_n: None,
+            min_confidence: 0.0,
+            tie_break: TieBreak::LiftThenSupport,
+        }
+    }
+}
+
+/// # the order of recommended items with the same best conf
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TieBreak {
+    /// the higher lift first, then the higher sup, then by item name
+    LiftThenSupport,
+    /// by item name only
+    Lexicographic,
+}
+
+impl RecommendationIndex {
+    /// ## the items to recommend for a basket
+    ///
+    /// every rule whose from is in the basket fires, and the items of its to not yet in the basket
+    /// are recommended, ranked by the highest conf of the rules recommending them
+    ///
+    /// same as recommend_with and the default RecommendOptions
+    pub fn recommend(&self, basket: &[String]) -> Vec<String> {
+        self.recommend_with(basket, RecommendOptions::default())
+    }
+
+    /// ## the items to recommend for a basket, ranked and cut by options
+    ///
+    /// each item keeps the best rule recommending it, compared by conf and then as options.tie_break says,
+    /// the order is deterministic whatever the order the rules were indexed in
+    pub fn recommend_with(&self, basket: &[String], options: RecommendOptions) -> Vec<String> {
+
+        // the (conf, lift, sup) of the best rule recommending each item
+        let mut best: HashMap<&String, (f64, f64, f64)> = HashMap::new();
+
+        let by_rank = |a: &(f64, f64, f64), b: &(f64, f64, f64)| match options.tie_break {
+            TieBreak::LiftThenSupport => a.0.total_cmp(&b.0).then(a.1.total_cmp(&b.1)).then(a.2.total_cmp(&b.2)),
+            TieBreak::Lexicographic => a.0.total_cmp(&b.0),
+        };
+
+        // sorted like the from of the rules
+        let mut sorted_basket = basket.to_vec();
+        sorted_basket.sort();
+        sorted_basket.dedup();
+
+        for (from, consequents) in self.rules.iter() {
+            if !subset_of(from, &sorted_basket) {
+                continue;
+            }
+
+            for (to, conf, lift, sup) in consequents.iter().filter(|x| x.1 >= options.min_confidence) {
+                for item in to.iter().filter(|&x| !basket.contains(x)) {
+                    let entry = best.entry(item).or_insert((*conf, *lift, *sup));
+                    if by_rank(&(*conf, *lift, *sup), entry).is_gt() {
+                        *entry = (*conf, *lift, *sup);
+                    }
+                }
+            }
+        }
+
+        let mut recommendations: Vec<(&String, (f64, f64, f64))> = best.into_iter().collect();
+        recommendations.sort_by(|a, b| by_rank(&b.1, &a.1).then_with(|| a.0.cmp(b.0)));
+
+        recommendations.into_iter()
+            .take(options.top_n.unwrap_or(usize::MAX))
+            .map(|x| x.0.clone())
+            .collect()
+    }
+}
+
+/// group association rules by their from into a RecommendationIndex
+pub fn build_recommendation_index(rules: Vec<AssociationRule>) -> RecommendationIndex {
+
+    let mut index = RecommendationIndex::default();
+
+    for rule in rules {
+        index.rules.entry(rule.from).or_default().push((rule.to, rule.conf, rule.lift, rule.sup));
+    }
+
+    for consequents in index.rules.values_mut() {
+        consequents.sort_by(|a, b| b.1.total_cmp(&a.1));
+    }
+
+    index
+}
+
+/// ## the maximal FrequentSets, those without any frequent superset
+///
+/// every subset of a FrequentSet is frequent, so it is enough to look at the sets one item larger:
+/// each of them marks its subsets one item smaller as not maximal
+///
+/// fre_sets has to hold all the FrequentSets of a run, as mined without required_items, the result keeps their order
+pub fn maximal_frequent_sets<I: Item>(fre_sets: &[FrequentSet<I>]) -> Vec<FrequentSet<I>> {
+
+    let mut covered: CountSet<Vec<I>> = CountSet::default();
+
+    for fre_set in fre_sets.iter().filter(|x| x.degree >= 2) {
+        for skip in 0..fre_set.degree {
+            covered.insert(without_item(&fre_set.items, skip));
+        }
+    }
+
+    fre_sets.iter().filter(|x| !covered.contains(&x.items)).cloned().collect()
+}
+
+/// ## the closed FrequentSets, those without a superset of the same count
+///
+/// counts only shrink when adding items, so it is enough to compare with the sets one item larger, see is_closed,
+/// the closed sets and their counts give the count of every FrequentSet back, as the largest count of a closed superset
+///
+/// fre_sets has to hold all the FrequentSets of a run, as mined without required_items, the result keeps their order
+pub fn closed_frequent_sets<I: Item>(fre_sets: &[FrequentSet<I>]) -> Vec<FrequentSet<I>> {
+    fre_sets.iter().filter(|x| is_closed(x, fre_sets)).cloned().collect()
+}
+
+/// ## the minimal generators among fre_sets
+///
+/// a generator is an itemset with no proper subset of the same count
+///
+/// the closure of an itemset X is the largest superset with the same count as X, and is a closed itemset,
+/// every closed itemset is the closure of one or more minimal generators, so generators and closed
+/// itemsets together give the minimal non-redundant rules generator -> closure \ generator
+///
+/// since counts only shrink when adding items, it is enough to compare with the subsets one item smaller
+///
+/// the empty set is never in fre_sets, so every 1-FrequentSet is taken as a generator
+pub fn minimal_generators<I: Item>(fre_sets: &[FrequentSet<I>]) -> Vec<FrequentSet<I>> {
+
+    let index = support_index(fre_sets);
+
+    fre_sets.iter().filter(|fre_set| {
+        if fre_set.degree == 1 {
+            return true;
+        }
+
+        // no subset with one item less has the same count
+        (0..fre_set.degree).all(|skip| {
+            let subset = without_item(&fre_set.items, skip);
+
+            match index.get(subset.as_slice()) {
+                Some(sub_fre_set) => sub_fre_set.count != fre_set.count,
+                None => true,
+            }
+        })
+    }).cloned().collect()
+}
+
+/// ## the minimal non-redundant association rules
+///
+/// all rules X -> Y of the full rule set can be derived from this basis, with their support and confidence:
+///
+/// exact rules (conf = 1): g -> closure(g) \ g, for every minimal generator g which is not closed
+///
+/// approximate rules: g -> f \ g, for every minimal generator g and every closed itemset f strictly
+/// containing closure(g), as long as count(f) / count(g) >= min_conf
+///
+/// the closure of g is the largest FrequentSet containing g with the same count, see [`minimal_generators`]
+///
+/// since only generators are used as antecedents and only closed itemsets as the union,
+/// the basis is much smaller than the full rule set on dense data
+pub fn non_redundant_rules<I: Item>(fre_sets: &[FrequentSet<I>], min_conf: f64, txn_num: usize) -> Vec<AssociationRule<I>> {
+
+    let mut rules: Vec<AssociationRule<I>> = Vec::new();
+
+    let index = support_index(fre_sets);
+    let closed_sets: Vec<&FrequentSet<I>> = fre_sets.iter().filter(|x| is_closed(x, fre_sets)).collect();
+
+    for generator in minimal_generators(fre_sets).iter() {
+
+        // the closure is the largest superset with the same count
+        let closure = fre_sets.iter()
+            .filter(|x| x.count == generator.count && subset_of(&generator.items, &x.items))
+            .max_by_key(|x| x.degree)
+            .unwrap();
+
+        for closed_set in closed_sets.iter() {
+
+            // exact rule from the closure itself, approximate rules from closed supersets of the closure
+            if closed_set.degree <= generator.degree || !subset_of(&closure.items, &closed_set.items) {
+                continue;
+            }
+
+            let conf = closed_set.count as f64 / generator.count as f64;
+
+            if conf >= min_conf {
+                let to: Vec<I> = closed_set.items.iter().filter(|&x| !generator.items.contains(x)).cloned().collect();
+                // only missing when fre_sets lack subsets, e.g. after required_items
+                let Some(to_count) = index.get(to.as_slice()).map(|x| x.count) else {
+                    continue;
+                };
+                let sup = closed_set.count as f64 / txn_num as f64;
+
+                rules.push(AssociationRule {
+                    from: generator.items.clone(),
+                    to,
+                    sup,
+                    conf,
+                    lift: conf * txn_num as f64 / to_count as f64,
+                    conviction: conviction(to_count as f64 / txn_num as f64, conf),
+                    leverage: sup - generator.count as f64 / txn_num as f64 * (to_count as f64 / txn_num as f64),
+                    antecedent_count: generator.count,
+                    consequent_count: to_count,
+                    union_count: closed_set.count,
+                });
+            }
+        }
+    }
+
+    rules
+}
+
+/// ## the share of CandicateSets of each degree which turned out frequent
+///
+/// return (degree, FrequentSets / CandicateSets) from AprioriResult.candidate_counts, a low hit rate
+/// means a lot of counting for nothing, the degrees without any CandicateSet are left out
+///
+/// with required_items, the dropped FrequentSets are missing and the hit rates are lower than they were
+pub fn lattice_hit_rates(fre_sets: &[FrequentSet], candidate_counts: &[(usize, usize)]) -> Vec<(usize, f64)> {
+    candidate_counts.iter()
+        .filter(|x| x.1 > 0)
+        .map(|&(degree, candi_num)| (degree, len_of_f_degree(fre_sets, degree) as f64 / candi_num as f64))
+        .collect()
+}
+
+/// ## the items anchoring the frequent patterns of each degree
+///
+/// for each degree, every item with how many FrequentSets of that degree contain it,
+/// sorted by that number descending, items in the same number of sets in lexicographic order
+pub fn core_items_by_degree(fre_sets: &[FrequentSet]) -> BTreeMap<usize, Vec<(String, usize)>> {
+
+    let mut appearances: BTreeMap<usize, HashMap<&String, usize>> = BTreeMap::new();
+
+    for fre_set in fre_sets.iter() {
+        let degree_appearances = appearances.entry(fre_set.degree).or_default();
+        for item in fre_set.items.iter() {
+            *degree_appearances.entry(item).or_insert(0) += 1;
+        }
+    }
+
+    appearances.into_iter().map(|(degree, degree_appearances)| {
+        let mut items: Vec<(String, usize)> = degree_appearances.into_iter().map(|x| (x.0.clone(), x.1)).collect();
+        items.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        (degree, items)
+    }).collect()
+}
+
+/// ## the Jaccard similarity |a ∩ b| / |a ∪ b| of two itemsets sorted in lexicographic order
+///
+/// 0 when both are empty
+pub fn itemset_jaccard(a: &[String], b: &[String]) -> f64 {
+
+    // walk both sorted itemsets at once
+    let (mut i, mut j, mut common) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                common += 1;
+                i += 1;
+                j += 1;
+            },
+        }
+    }
+
+    let union = a.len() + b.len() - common;
+    if union == 0 {
+        return 0.0;
+    }
+
+    common as f64 / union as f64
+}
+
+/// ## group the FrequentSets into themes of similar itemsets
+///
+/// single-linkage agglomerative clustering: two FrequentSets whose itemset_jaccard is >= threshold
+/// are in the same cluster, and so are the clusters they are in, until no pair across clusters is left
+///
+/// every pair is compared once, O(n^2 * degree) for n FrequentSets, and clusters are merged with union-find
+///
+/// return the clusters as indexes into fre_sets, each sorted, ordered by their first index,
+/// a FrequentSet similar to none is a cluster on its own
+pub fn cluster_itemsets(fre_sets: &[FrequentSet], threshold: f64) -> Vec<Vec<usize>> {
+
+    // union-find, every FrequentSet starts as its own cluster
+    let mut parent: Vec<usize> = (0..fre_sets.len()).collect();
+
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+
+    for i in 0..fre_sets.len() {
+        for j in i + 1..fre_sets.len() {
+            if itemset_jaccard(&fre_sets[i].items, &fre_sets[j].items) >= threshold {
+                let (root_i, root_j) = (root(&mut parent, i), root(&mut parent, j));
+                parent[root_i.max(root_j)] = root_i.min(root_j);
+            }
+        }
+    }
+
+    let mut clusters: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..fre_sets.len() {
+        clusters.entry(root(&mut parent, i)).or_default().push(i);
+    }
+
+    clusters.into_values().collect()
+}
+
+/// ## the n most similar item pairs by co-occurrence cosine
+///
+/// cos(X, Y) = count(X, Y) / sqrt(count(X) * count(Y))
+///
+/// only the frequent 2-itemsets are considered, since the count of any other pair is unknown,
+/// so are pairs whose items are missing from fre_sets, e.g. dropped by required_items,
+/// return (X, Y, cos) sorted by cos descending
+pub fn item_cosine_similarity(fre_sets: &[FrequentSet], n: usize) -> Vec<(String, String, f64)> {
+
+    let index = support_index(fre_sets);
+
+    let mut similarities: Vec<(String, String, f64)> = fre_sets.iter().filter(|x| x.degree == 2).filter_map(|pair| {
+        let x_count = index.get(&pair.items[0..1])?.count;
+        let y_count = index.get(&pair.items[1..2])?.count;
+        // multiplied as floats, the product of two large counts overflows usize
+        let cos = pair.count as f64 / (x_count as f64 * y_count as f64).sqrt();
+
+        Some((pair.items[0].clone(), pair.items[1].clone(), cos))
+    }).collect();
+
+    similarities.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+    similarities.truncate(n);
+
+    similarities
+}
+
+/// ## how directional every frequent pair is
+///
+/// asymmetry(A, B) = |conf(A -> B) - conf(B -> A)|
+///
+/// return (A, B, asymmetry) sorted by asymmetry descending, A is the side whose rule A -> B is the more confident,
+/// so a high asymmetry reads as "A implies B much more than B implies A",
+/// pairs whose items are missing from fre_sets, e.g. dropped by required_items, are left out
+pub fn confidence_asymmetry(fre_sets: &[FrequentSet], txn_num: usize) -> Vec<(String, String, f64)> {
+
+    let index = support_index(fre_sets);
+    let support = |items: &[String]| index.get(items).map(|x| x.count as f64 / txn_num as f64);
+
+    let mut asymmetries: Vec<(String, String, f64)> = fre_sets.iter().filter(|x| x.degree == 2).filter_map(|pair| {
+        let pair_sup = pair.count as f64 / txn_num as f64;
+        let conf_0_1 = pair_sup / support(&pair.items[0..1])?;
+        let conf_1_0 = pair_sup / support(&pair.items[1..2])?;
+
+        if conf_0_1 >= conf_1_0 {
+            Some((pair.items[0].clone(), pair.items[1].clone(), conf_0_1 - conf_1_0))
+        } else {
+            Some((pair.items[1].clone(), pair.items[0].clone(), conf_1_0 - conf_0_1))
+        }
+    }).collect();
+
+    asymmetries.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+
+    asymmetries
+}
+
+/// ## P(item | context) for every item, given a context basket
+///
+/// P(item | context) = support(context ∪ {item}) / support(context)
+///
+/// only items whose union with context is frequent are returned, sorted by the conditional support descending,
+/// nothing is returned when context itself is not frequent
+///
+/// an empty context gives the plain support of every frequent item, hence txn_num
+pub fn conditional_item_support(context: &[String], fre_sets: &[FrequentSet], txn_num: usize) -> Vec<(String, f64)> {
+
+    let index = support_index(fre_sets);
+
+    let mut context = context.to_vec();
+    context.sort();
+    context.dedup();
+
+    let context_count = if context.is_empty() {
+        txn_num
+    } else {
+        match index.get(context.as_slice()) {
+            Some(context_fre_set) => context_fre_set.count,
+            None => return Vec::new(),
+        }
+    };
+
+    let mut supports: Vec<(String, f64)> = Vec::new();
+
+    for item_fre_set in fre_sets.iter().filter(|x| x.degree == 1 && !context.contains(&x.items[0])) {
+        let mut joint = context.clone();
+        joint.push(item_fre_set.items[0].clone());
+        joint.sort();
+
+        if let Some(joint_fre_set) = index.get(joint.as_slice()) {
+            supports.push((item_fre_set.items[0].clone(), joint_fre_set.count as f64 / context_count as f64));
+        }
+    }
+
+    supports.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    supports
+}
+
+/// ## the confidence of antecedent -> consequent after adding each item to antecedent
+///
+/// conf(antecedent ∪ {item} -> consequent) = support(antecedent ∪ {item} ∪ consequent) / support(antecedent ∪ {item})
+///
+/// every frequent item out of both sides is a candidate, but only those whose union with both sides is frequent
+/// have a known confidence and are returned, sorted by the new confidence descending
+pub fn marginal_item_effect(antecedent: &[String], consequent: &[String], fre_sets: &[FrequentSet], txn_num: usize) -> Vec<(String, f64)> {
+
+    let index = support_index(fre_sets);
+    let support = |items: &[String]| index.get(items).map(|x| x.count as f64 / txn_num as f64);
+
+    let mut effects: Vec<(String, f64)> = Vec::new();
+
+    for item_fre_set in fre_sets.iter().filter(|x| x.degree == 1) {
+        let item = &item_fre_set.items[0];
+        if antecedent.contains(item) || consequent.contains(item) {
+            continue;
+        }
+
+        let mut from: Vec<String> = antecedent.to_vec();
+        from.push(item.clone());
+        from.sort();
+        from.dedup();
+
+        let mut joint = from.clone();
+        joint.extend(consequent.iter().cloned());
+        joint.sort();
+        joint.dedup();
+
+        if let (Some(from_sup), Some(joint_sup)) = (support(&from), support(&joint)) {
+            effects.push((item.clone(), joint_sup / from_sup));
+        }
+    }
+
+    effects.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    effects
+}
+
+/// # the itemsets which are not frequent, but all of whose subsets one item smaller are
+///
+/// sets: these itemsets with their counts, by degree
+///
+/// an itemset can only become frequent when transactions are added if it is already frequent,
+/// or is on the negative border, or is a superset of a set of the border which became frequent,
+/// so the FrequentSets and the border are enough to update the FrequentSets, see [`update_frequent_sets`]
+#[derive(Debug, Default)]
+pub struct NegativeBorder {
+    sets: Vec<CandicateSet>,
+}
+
+impl NegativeBorder {
+    /// the sets of the border with their counts
+    pub fn sets(&self) -> &[CandicateSet] {
+        &self.sets
+    }
+}
+
+/// ## the negative border of fre_sets, counted over txn_set
+///
+/// fre_sets has to hold all the FrequentSets of txn_set, as mined without time decay
+pub fn negative_border(fre_sets: &[FrequentSet], txn_set: &[Txn]) -> NegativeBorder {
+
+    let index = support_index(fre_sets);
+
+    let count_of = |items: &[String]| count_containing(items, txn_set);
+
+    level_wise(txn_set, count_of, |items, _| index.contains_key(items)).1
+}
+
+/// ## update the FrequentSets after new transactions arrived, without mining from scratch
+///
+/// prev_frequent and prev_negative_border are the FrequentSets and the negative border of the old transactions,
+/// all_txns holds the old and the new transactions, new_txns only the new ones
+///
+/// like the Borders algorithm, the counts of prev_frequent and prev_negative_border are brought up to date
+/// with one scan of new_txns, then the levels are rebuilt from these counts, every candidate not known yet,
+/// i.e. a superset of a border set which became frequent, is counted over all_txns
+///
+/// when no border set became frequent, all_txns is only scanned once for the 1-itemsets
+///
+/// return the same FrequentSets as mining all_txns at min_sup, with the new negative border for the next update
+pub fn update_frequent_sets(prev_frequent: &[FrequentSet], prev_negative_border: &NegativeBorder, new_txns: &[Txn], all_txns: &[Txn], min_sup: f64) -> (Vec<FrequentSet>, NegativeBorder) {
+
+    let min_sup = MinSupport::new(min_sup, txn_count(all_txns), &txn_weights(all_txns, None), false);
+
+    // the old counts plus the counts in new_txns
+    let mut known: CountMap<&[String], usize> = prev_frequent.iter().map(|x| (x.items.as_slice(), x.count))
+        .chain(prev_negative_border.sets.iter().map(|x| (x.items.as_slice(), x.count)))
+        .collect();
+
+    for txn in new_txns.iter() {
+        for (items, count) in known.iter_mut() {
+            if subset_of(items, &txn.items) {
+                *count += txn.weight;
+            }
+        }
+    }
+
+    let count_of = |items: &[String]| match known.get(items) {
+        Some(&count) => count,
+        None => count_containing(items, all_txns),
+    };
+
+    level_wise(all_txns, count_of, |_, count| min_sup.is_frequent(count, count as f64))
+}
+
+/// # a rough idea of how heavy a run will be, see [`estimate_complexity`]
+///
+/// distinct_items: the number of distinct items
+///
+/// frequent_items: the number of items with support >= min_sup
+///
+/// density: the average txn length over distinct_items
+///
+/// max_degree: the highest degree a FrequentSet is expected to reach
+///
+/// estimated_candidates: the order of magnitude of CandicateSets to count over all degrees
+///
+/// class: what to expect from the run
+#[derive(Clone, Debug)]
+pub struct ComplexityEstimate {
+    pub distinct_items: usize,
+    pub frequent_items: usize,
+    pub density: f64,
+    pub max_degree: usize,
+    pub estimated_candidates: f64,
+    pub class: RuntimeClass,
+}
+
+/// # the runtime class of a ComplexityEstimate
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuntimeClass {
+    /// seconds at most
+    Quick,
+    /// minutes to hours
+    Slow,
+    /// not worth launching, raise min_sup
+    LikelyInfeasible,
+}
+
+/// ## estimate the candidate counts of a run at min_sup before launching it
+///
+/// only the 1-itemsets are counted, and items are taken as independent: a k-itemset is expected to be
+/// frequent when the product of its item supports is >= min_sup
+///
+/// max_degree is the largest k for which the k most frequent items pass this test
+///
+/// all pairs of frequent items are candidates, and a k-itemset with k >= 3 is a candidate when all its
+/// subsets one item smaller are expected frequent, correlated items like in dense datasets
+/// give more candidates than estimated
+///
+/// the candidates are counted, not enumerated, see count_candidates, so the estimate takes the same short time
+/// whether it finds a hundred candidates or 2^40
+///
+/// the class comes from the counting work, candidates * txn count * average txn length:
+/// below 1e9 is Quick, below 1e12 is Slow, anything above is LikelyInfeasible
+pub fn estimate_complexity(txn_set: &[Txn], min_sup: f64) -> ComplexityEstimate {
+
+    let counts = item_counts(txn_set);
+    let txn_num = txn_count(txn_set).max(1) as f64;
+
+    // item_counts is sorted by count descending
+    let supports: Vec<f64> = counts.iter().map(|x| x.1 as f64 / txn_num).filter(|&x| x >= min_sup).collect();
+    let frequent_items = supports.len();
+
+    let avg_len = txn_set.iter().map(|x| x.items.len() * x.weight).sum::<usize>() as f64 / txn_num;
+    let density = if counts.is_empty() { 0.0 } else { avg_len / counts.len() as f64 };
+
+    let mut max_degree = 0;
+    let mut product = 1.0;
+    for support in supports.iter() {
+        product *= support;
+        if product < min_sup {
+            break;
+        }
+        max_degree += 1;
+    }
+
+    // all the pairs, then the larger candidates
+    let estimated_candidates = (frequent_items * frequent_items.saturating_sub(1) / 2) as f64 + count_candidates(&supports, min_sup);
+
+    let work = estimated_candidates * txn_num * avg_len;
+    let class = if work < 1e9 {
+        RuntimeClass::Quick
+    } else if work < 1e12 {
+        RuntimeClass::Slow
+    } else {
+        RuntimeClass::LikelyInfeasible
+    };
+
+    ComplexityEstimate {
+        distinct_items: counts.len(),
+        frequent_items,
+        density,
+        max_degree,
+        estimated_candidates,
+        class,
+    }
+}
+
+/// ## the number of expected candidates of 3 or more items among items of these supports, sorted descending
+///
+/// the weakest subset of a candidate is the one without its most frequent item, the first one, so a candidate
+/// is a first item and at least 2 later items whose support product is >= min_sup
+///
+/// the product is a sum of -ln(support) below -ln(min_sup), which is rounded to CANDIDATE_BUCKETS buckets,
+/// then the subsets of the items after each first item are counted by bucket and size like a knapsack,
+/// in O(items * CANDIDATE_BUCKETS) however many candidates there are
+fn count_candidates(supports: &[f64], min_sup: f64) -> f64 {
+
+    let budget = -min_sup.ln();
+    let buckets: Vec<usize> = supports.iter().map(|support| {
+        if budget > 0.0 { (-support.ln() / budget * CANDIDATE_BUCKETS as f64).round() as usize } else { 0 }
+    }).collect();
+
+    // subsets[bucket][size]: the subsets of the items seen so far of that bucket, of 0, 1 and 2 or more items
+    let mut subsets: Vec<[f64; 3]> = vec![[0.0; 3]; CANDIDATE_BUCKETS + 1];
+    subsets[0][0] = 1.0;
+    let mut count = 0.0;
+
+    // from the rarest item, so subsets holds the items after first
+    for &first in buckets.iter().rev() {
+        count += subsets.iter().map(|x| x[2]).sum::<f64>();
+
+        // adding first to every subset which stays within the budget, the largest buckets first
+        for bucket in (first..=CANDIDATE_BUCKETS).rev() {
+            let [none, one, more] = subsets[bucket - first];
+            subsets[bucket][1] += none;
+            subsets[bucket][2] += one + more;
+        }
+    }
+
+    count
+}
+
+/// the resolution of -ln(support) in count_candidates
+const CANDIDATE_BUCKETS: usize = 1024;
+
+/// ## the FrequentSets and negative border of txn_set, level by level
+///
+/// every item is a 1-CandicateSet, then the (degree + 1)-CandicateSets are generated from the degree-FrequentSets
+/// by get_candi_from_f
+///
+/// count_of gives the count of a CandicateSet above degree 1, is_frequent decides which side it goes to
+fn level_wise<C, F>(txn_set: &[Txn], mut count_of: C, is_frequent: F) -> (Vec<FrequentSet>, NegativeBorder)
+where
+    C: FnMut(&[String]) -> usize,
+    F: Fn(&[String], usize) -> bool,
+{
+    let mut fre_sets: Vec<FrequentSet> = Vec::new();
+    let mut border = NegativeBorder::default();
+
+    // in lexicographic order for the join, like init_fre_set
+    let mut candicate_set_1: Vec<(String, (usize, f64))> = create_candicate_set_1(txn_set, &txn_weights(txn_set, None)).into_iter().collect();
+    candicate_set_1.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut candidates: Vec<CandicateSet> = candicate_set_1.into_iter()
+        .map(|(item, (count, _))| CandicateSet { degree: 1, items: vec![item], count, weighted_count: count as f64 })
+        .collect();
+
+    let mut degree = 1;
+
+    while !candidates.is_empty() {
+        for candi_set in candidates {
+            if is_frequent(&candi_set.items, candi_set.count) {
+                fre_sets.push(FrequentSet {
+                    degree: candi_set.degree,
+                    items: candi_set.items,
+                    count: candi_set.count,
+                    weighted_count: candi_set.weighted_count,
+                    tids: Vec::new(),
+                });
+            } else {
+                border.sets.push(candi_set);
+            }
+        }
+
+        if len_of_f_degree(&fre_sets, degree) == 0 {
+            break;
+        }
+
+        let mut next: Vec<CandicateSet> = get_candi_from_f(&fre_sets, degree);
+
+        for candi_set in next.iter_mut() {
+            candi_set.count = count_of(&candi_set.items);
+            candi_set.weighted_count = candi_set.count as f64;
+        }
+
+        candidates = next;
+        degree += 1;
+    }
+
+    (fre_sets, border)
+}
+
+/// a FrequentSet is closed when no FrequentSet with one more item has the same count
+fn is_closed<I: Item>(fre_set: &FrequentSet<I>, fre_sets: &[FrequentSet<I>]) -> bool {
+    !fre_sets.iter().any(|x| x.degree == fre_set.degree + 1 && x.count == fre_set.count && subset_of(&fre_set.items, &x.items))
+}
+
+/// ## look up FrequentSets by their items
+///
+/// the items of every FrequentSet are sorted, so the slice of items is a canonical key
+fn support_index<I: Item>(fre_sets: &[FrequentSet<I>]) -> CountMap<&[I], &FrequentSet<I>> {
+    fre_sets.iter().map(|x| (x.items.as_slice(), x)).collect()
+}
+
+/// the bytes of the buffer of items, and the bytes owned_bytes says every item owns
+fn items_bytes<I: Item>(items: &Vec<I>, owned_bytes: fn(&I) -> usize) -> usize {
+    items.capacity() * std::mem::size_of::<I>() + items.iter().map(owned_bytes).sum::<usize>()
+}
+
+/// the bytes of fre_set, its items and its tids
+fn fre_set_bytes<I: Item>(fre_set: &FrequentSet<I>, owned_bytes: fn(&I) -> usize) -> usize {
+    size_of_val(fre_set) + items_bytes(&fre_set.items, owned_bytes) + fre_set.tids.capacity() * std::mem::size_of::<usize>()
+}
+
+/// the bytes of rule and its from and to
+fn rule_bytes<I: Item>(rule: &AssociationRule<I>, owned_bytes: fn(&I) -> usize) -> usize {
+    size_of_val(rule) + items_bytes(&rule.from, owned_bytes) + items_bytes(&rule.to, owned_bytes)
+}
+
+/// copy of items without the item at position skip
+fn without_item<I: Item>(items: &[I], skip: usize) -> Vec<I> {
+    items.iter().enumerate().filter(|x| x.0 != skip).map(|x| x.1.clone()).collect()
+}
+
+/// generate all association rules sorted by sort_rules_by_confidence, return the time it takes
+///
+/// total_weight: the support denominator, the number of transactions without time decay
+///
+/// with the parallel feature every thread takes every n-th FrequentSet, so the high degree sets at the end
+/// are spread over all threads, they share the read-only support_index and the rules are merged before the sort
+///
+/// with max_rules every thread keeps its own max_rules best, the best of all are among them
+///
+/// with count_rules_only nothing is pushed to association_rules_set, the rules are only counted
+fn generate_association_rules<I: Item>(fre_sets: &[FrequentSet<I>], config: &AprioriConfig<I>, association_rules_set: &mut Vec<AssociationRule<I>>, total_weight: f64) -> Duration {
+
+    // start
+    config.report(ProgressEvent::RulesStarted { min_conf: config.min_conf });
+    let rule_start_time = Instant::now();
+    let mut rule_count = 0;
+
+    #[cfg(feature = "parallel")]
+    {
+        let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+        let index = support_index(fre_sets);
+        let index = &index;
+
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..threads).map(|thread| scope.spawn(move || {
+                gather_rules(config, |emit| {
+                    for fre_set in fre_sets.iter().skip(thread).step_by(threads) {
+                        if for_each_rule_of(fre_set, index, config, total_weight, emit).is_break() {
+                            break;
+                        }
+                    }
+                })
+            })).collect();
+
+            for handle in handles {
+                let (rules, count) = handle.join().expect("a rule generation thread panicked");
+                association_rules_set.extend(rules);
+                rule_count += count;
+            }
+        });
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    {
+        let (rules, count) = gather_rules(config, |emit| for_each_rule(fre_sets, fre_sets, config, total_weight, emit));
+        association_rules_set.extend(rules);
+        rule_count += count;
+    }
+
+    sort_rules_by_confidence(association_rules_set);
+    association_rules_set.truncate(config.max_rules.unwrap_or(usize::MAX));
+
+    // end
+    let rule_duration = rule_start_time.elapsed();
+    let count = rule_count.min(config.max_rules.unwrap_or(usize::MAX));
+    config.report(ProgressEvent::RulesFinished { count, duration: rule_duration });
+
+    rule_duration
+}
+
+/// ## the rules generate hands to its emit, all of them, or the max_rules best by confidence_order in no order
+///
+/// the best are kept in a heap bounded to max_rules, whose top is the worst kept, so at most max_rules + 1
+/// rules are in memory at a time
+fn collect_rules<I: Item, G: FnOnce(&mut dyn FnMut(AssociationRule<I>) -> ControlFlow<()>)>(max_rules: Option<usize>, generate: G) -> Vec<AssociationRule<I>> {
+    let Some(max_rules) = max_rules else {
+        let mut rules: Vec<AssociationRule<I>> = Vec::new();
+        generate(&mut |rule| {
+            rules.push(rule);
+            ControlFlow::Continue(())
+        });
+        return rules;
+    };
+
+    let mut heap: std::collections::BinaryHeap<RankedRule<I>> = std::collections::BinaryHeap::new();
+    generate(&mut |rule| {
+        heap.push(RankedRule(rule));
+        if heap.len() > max_rules {
+            heap.pop();
+        }
+        ControlFlow::Continue(())
+    });
+
+    heap.into_vec().into_iter().map(|x| x.0).collect()
+}
+
+/// ## the rules generate emits, kept as collect_rules keeps them, with their number
+///
+/// with config.count_rules_only they are only counted and dropped as they come, nothing is kept,
+/// the count is then capped at max_rules like the kept rules would be
+fn gather_rules<I: Item, G: FnOnce(&mut dyn FnMut(AssociationRule<I>) -> ControlFlow<()>)>(config: &AprioriConfig<I>, generate: G) -> (Vec<AssociationRule<I>>, usize) {
+    if !config.count_rules_only {
+        let rules = collect_rules(config.max_rules, generate);
+        let count = rules.len();
+        return (rules, count);
+    }
+
+    let mut count = 0;
+    generate(&mut |_| {
+        count += 1;
+        ControlFlow::Continue(())
+    });
+
+    (Vec::new(), count.min(config.max_rules.unwrap_or(usize::MAX)))
+}
+
+/// ## hand every association rule of the sources to emit, as it is found
+///
+/// fre_sets holds the sources and all their subsets, to look up the support of from and to,
+/// they are indexed once by support_index so every lookup is a hash instead of a scan
+///
+/// generation stops at the first ControlFlow::Break emit returns, no rule is generated after it
+fn for_each_rule<'a, I: Item + 'a, S, E>(sources: S, fre_sets: &[FrequentSet<I>], config: &AprioriConfig<I>, total_weight: f64, emit: &mut E)
+where
+    S: IntoIterator<Item = &'a FrequentSet<I>>,
+    E: FnMut(AssociationRule<I>) -> ControlFlow<()> + ?Sized,
+{
+    let index = support_index(fre_sets);
+
+    // iterate over non-empty real subset of each FrequentSet
+    for fre_set in sources {
+        if for_each_rule_of(fre_set, &index, config, total_weight, emit).is_break() {
+            return;
+        }
+    }
+}
+
+/// ## hand every association rule of one FrequentSet to emit
+///
+/// index: the support_index of the FrequentSets, it holds every subset of fre_set
+///
+/// ControlFlow::Break once emit breaks, the splits left are not tried
+fn for_each_rule_of<I: Item, E: FnMut(AssociationRule<I>) -> ControlFlow<()> + ?Sized>(fre_set: &FrequentSet<I>, index: &CountMap<&[I], &FrequentSet<I>>, config: &AprioriConfig<I>, total_weight: f64, emit: &mut E) -> ControlFlow<()> {
+    let degree = fre_set.degree;
+
+    // freset whose degree is 1, is ignored
+    if degree == 1 {
+        return ControlFlow::Continue(());
+    }
+
+    // only mined to support the lookups of other sets
+    if !config.has_required_item(&fre_set.items) {
+        return ControlFlow::Continue(());
+    }
+
+    // no split of it has consequent_item in its to
+    if config.consequent_item.as_ref().is_some_and(|item| !fre_set.items.contains(item)) {
+        return ControlFlow::Continue(());
+    }
+
+    // too many subsets to enumerate, or single_item_consequents, only take one item at a time as to
+    let above_soft_limit = config.rule_degree_soft_limit.is_some_and(|limit| degree > limit);
+    if above_soft_limit {
+        config.report(ProgressEvent::RuleDegreeSoftLimit { degree });
+    }
+
+    if above_soft_limit || config.single_item_consequents {
+
+        for pos in 0..degree {
+            let from = without_item(&fre_set.items, pos);
+            let to = vec![fre_set.items[pos].clone()];
+
+            if !config.allows_split(&from, &to) {
+                continue;
+            }
+
+            emit_rule_if_confident(fre_set, from, to, index, config, total_weight, emit)?;
+        }
+
+        return ControlFlow::Continue(());
+    }
+
+    // iterate over all non-empty real subsets of fre_set.items, 2^n - 2 in total
+    // the bitmap is one bool per item, so no degree overflows an integer
+    let mut bitmap = vec![false; degree];
+
+    while next_bitmap(&mut bitmap) {
+
+        // association rule: from -> to
+        let mut from: Vec<I> = Vec::new();
+        let mut to: Vec<I> = Vec::new();
+
+        // bitmap: true for from, the rest is for to
+        for (item, &in_from) in fre_set.items.iter().zip(&bitmap) {
+            if in_from {
+                from.push(item.clone());
+            } else {
+                to.push(item.clone());
+            }
+        }
+
+        if !config.allows_split(&from, &to) {
+            continue;
+        }
+
+        emit_rule_if_confident(fre_set, from, to, index, config, total_weight, emit)?;
+    }
+
+    ControlFlow::Continue(())
+}
+
+/// ## step bitmap to the next subset, counting in binary with bitmap[0] as the lowest bit
+///
+/// the subsets come in the same order as the integers 1..2^n - 1, false once every bit would be set,
+/// since the full set is not a real subset
+fn next_bitmap(bitmap: &mut [bool]) -> bool {
+    let Some(pos) = bitmap.iter().position(|x| !x) else {
+        return false;
+    };
+
+    bitmap[..pos].fill(false);
+    bitmap[pos] = true;
+
+    bitmap.contains(&false)
+}
+
+/// ## turn the split from -> to of fre_set into an AssociationRule and emit it if its conf >= min_conf
+///
+/// index: the support_index of the FrequentSets, from and to are both in it when every subset of fre_set is frequent
+///
+/// FrequentSets fed from outside may be inconsistent: a from or to missing from index is skipped and reported
+/// as ProgressEvent::MissingSubset, a from without support as ProgressEvent::ZeroAntecedentSupport
+/// instead of dividing by zero
+///
+/// what emit returns, ControlFlow::Continue when there is no rule to emit
+fn emit_rule_if_confident<I: Item, E: FnMut(AssociationRule<I>) -> ControlFlow<()> + ?Sized>(fre_set: &FrequentSet<I>, from: Vec<I>, to: Vec<I>, index: &CountMap<&[I], &FrequentSet<I>>, config: &AprioriConfig<I>, total_weight: f64, emit: &mut E) -> ControlFlow<()> {
+
+    let Some(&from_fre_set) = index.get(from.as_slice()) else {
+        config.report(ProgressEvent::MissingSubset { degree: fre_set.degree });
+        return ControlFlow::Continue(());
+    };
+    if from_fre_set.weighted_count <= 0.0 {
+        config.report(ProgressEvent::ZeroAntecedentSupport { degree: fre_set.degree });
+        return ControlFlow::Continue(());
+    }
+
+    // calculate conf for the rule
+    let min_conf = config.min_conf;
+    let conf = fre_set.weighted_count / from_fre_set.weighted_count;
+
+    // if conf >= min_conf, this rule is an association rule!
+    if conf >= min_conf {
+        let Some(&to_fre_set) = index.get(to.as_slice()) else {
+            config.report(ProgressEvent::MissingSubset { degree: fre_set.degree });
+            return ControlFlow::Continue(());
+        };
+        let sup = fre_set.weighted_count / total_weight;
+        let to_sup = to_fre_set.weighted_count / total_weight;
+
+        let new_rule = AssociationRule {
+            from,
+            to,
+            sup,
+            conf,
+            lift: conf * total_weight / to_fre_set.weighted_count,
+            conviction: conviction(to_sup, conf),
+            leverage: sup - from_fre_set.weighted_count / total_weight * to_sup,
+            antecedent_count: from_fre_set.count,
+            consequent_count: to_fre_set.count,
+            union_count: fre_set.count,
+        };
+
+        return emit(new_rule);
+    }
+
+    ControlFlow::Continue(())
+}
+
+/// (1 - to_sup) / (1 - conf), a rule with conf = 1 is never wrong, so its conviction is infinite rather than NaN
+fn conviction(to_sup: f64, conf: f64) -> f64 {
+    if conf >= 1.0 {
+        f64::INFINITY
+    } else {
+        (1.0 - to_sup) / (1.0 - conf)
+    }
+}
+
+/// generate all FrequentSets from 1-FrequentSet, return the time it takes
+///
+/// traced_count receives the count of config.trace_itemset if it is generated as a CandicateSet,
+/// candidate_counts the (degree, number of CandicateSets) of every degree counted
+fn generate_all_fre_sets<I: Item>(fre_sets: &mut Vec<FrequentSet<I>>, txn_set: &[Txn<I>], weights: &[f64], min_sup: &MinSupport, config: &AprioriConfig<I>, traced_count: &mut Option<usize>, candidate_counts: &mut Vec<(usize, usize)>) -> Duration {
+
+    // start
+    let fre_start_time = Instant::now();
+    config.report(ProgressEvent::FrequentSetsStarted { min_count: min_sup.min_count, total_weight: min_sup.total_weight });
+
+    // every CandicateSet is joined from 1-FrequentSets, pruning the forbidden items here keeps them out of all degrees
+    if !config.forbidden_items.is_empty() {
+        fre_sets.retain(|x| !x.items.iter().any(|item| config.forbidden_items.contains(item)));
+    }
+
+    // one signature per txn, computed once for all degrees
+    let signatures: Option<Vec<u64>> = if config.bloom_prefilter {
+        Some(txn_set.iter().map(|x| item_signature(&x.items)).collect())
+    } else {
+        None
+    };
+
+    // calculate the len of FrequentSet based on degree
+    let mut degree = 1;
+    let mut len_of_f = len_of_f_degree(fre_sets, degree);
+
+    // when f of degree is empty, or degree is max_degree, the loop is over
+    while len_of_f > 0 && config.max_degree.is_none_or(|max_degree| degree < max_degree) {
+        // candi_sets.count = 0 at this moment
+        // len of set in candi_sets is degree + 1
+        let candi_batches: Box<dyn Iterator<Item = Vec<CandicateSet<I>>>> = match config.candidate_batch_size {
+            Some(batch_size) => {
+                config.report(ProgressEvent::DegreeStarted { degree: degree + 1, frequent_below: len_of_f, candidates: None });
+                Box::new(CandidateBatches::new(fre_sets, degree, batch_size))
+            },
+            None => {
+                let candi_sets = get_candi_from_f(fre_sets, degree);
+                config.report(ProgressEvent::DegreeStarted { degree: degree + 1, frequent_below: len_of_f, candidates: Some(candi_sets.len()) });
+                Box::new(std::iter::once(candi_sets))
+            },
+        };
+
+        let mut candi_num = 0;
+
+        for candi_batch in candi_batches {
+            candi_num += candi_batch.len();
+
+            for candi_set in count_batch(candi_batch, txn_set, weights, signatures.as_deref(), min_sup, config.membership_only) {
+                if config.trace_itemset.as_ref() == Some(&candi_set.items) {
+                    *traced_count = Some(candi_set.count);
+                }
+
+                // if candi_set.items >= min_sup
+                // convert it to FrequentSet and add it to fre_sets
+                if min_sup.is_frequent(candi_set.count, candi_set.weighted_count) {
+                    let new_fre = FrequentSet {
+                        degree: candi_set.degree,
+                        items: candi_set.items,
+                        count: candi_set.count,
+                        weighted_count: candi_set.weighted_count,
+                        tids: Vec::new(),
+                    };
+
+                    fre_sets.push(new_fre);
+                }
+            }
+        }
+
+        candidate_counts.push((degree + 1, candi_num));
+
+        degree += 1;
+        len_of_f = len_of_f_degree(fre_sets, degree);
+        config.report(ProgressEvent::DegreeFinished { degree, candidates: candi_num, frequent: len_of_f });
+
+    }
+
+    // end
+    let fre_duration = fre_start_time.elapsed();
+    config.report(ProgressEvent::FrequentSetsFinished { count: fre_sets.len(), duration: fre_duration });
+
+    fre_duration
+}
+
+/// ## count every CandicateSet of batch over txn_set
+///
+/// signatures: the bloom filter of every txn with bloom_prefilter, see item_signature
+///
+/// with the parallel feature the batch is split into one chunk per thread, every CandicateSet is counted
+/// on its own so the threads share nothing but the read-only txn_set, and the batch keeps its order
+fn count_batch<I: Item>(mut batch: Vec<CandicateSet<I>>, txn_set: &[Txn<I>], weights: &[f64], signatures: Option<&[u64]>, min_sup: &MinSupport, membership_only: bool) -> Vec<CandicateSet<I>> {
+
+    #[cfg(feature = "parallel")]
+    {
+        let threads = std::thread::available_parallelism().map_or(1, |x| x.get());
+        let chunk_size = batch.len().div_ceil(threads).max(1);
+
+        std::thread::scope(|scope| {
+            for chunk in batch.chunks_mut(chunk_size) {
+                scope.spawn(move || {
+                    for candi_set in chunk.iter_mut() {
+                        count_candidate(candi_set, txn_set, weights, signatures, min_sup, membership_only);
+                    }
+                });
+            }
+        });
+    }
+
+    #[cfg(not(feature = "parallel"))]
+    for candi_set in batch.iter_mut() {
+        count_candidate(candi_set, txn_set, weights, signatures, min_sup, membership_only);
+    }
+
+    batch
+}
+
+/// count one CandicateSet over txn_set, membership_only stops as soon as it is known to be frequent
+fn count_candidate<I: Item>(candi_set: &mut CandicateSet<I>, txn_set: &[Txn<I>], weights: &[f64], signatures: Option<&[u64]>, min_sup: &MinSupport, membership_only: bool) {
+
+    let candi_signature = signatures.map(|_| item_signature(&candi_set.items));
+
+    for (i, (txn, weight)) in txn_set.iter().zip(weights).enumerate() {
+
+        // a bit of the candidate missing in the txn means an item is missing
+        if let (Some(signatures), Some(candi_signature)) = (signatures, candi_signature) {
+            if candi_signature & !signatures[i] != 0 {
+                continue;
+            }
+        }
+
+        // if candi_set.items is subset of txn.items
+        // candi_set.count += 1
+        if subset_of(&candi_set.items, &txn.items) {
+            candi_set.count += txn.weight;
+            candi_set.weighted_count += weight;
+
+            // already known to be frequent, the rest of the scan would only refine its count
+            if membership_only && min_sup.is_frequent(candi_set.count, candi_set.weighted_count) {
+                break;
+            }
+        }
+    }
+}
+
+/// ## fill the tids of every FrequentSet, the ids of the txns containing it
+///
+/// the 1-FrequentSets get theirs from one scan of txn_set, a larger set is joined from its subsets without
+/// one of its last two items, see join_fre_sets, and its tids are the intersection of theirs
+///
+/// fre_sets is in ascending degree, as mined, so both subsets are filled before the set,
+/// a set whose subsets are not both in fre_sets, e.g. FrequentSets fed from outside, has its txns scanned instead
+fn fill_tids<I: Item>(fre_sets: &mut [FrequentSet<I>], txn_set: &[Txn<I>]) {
+
+    let mut item_tids: CountMap<&I, Vec<usize>> = CountMap::default();
+    for txn in txn_set.iter() {
+        for item in txn.items.iter() {
+            let tids = item_tids.entry(item).or_default();
+            // an item repeated in a txn is counted once
+            if tids.last() != Some(&txn.id) {
+                tids.push(txn.id);
+            }
+        }
+    }
+
+    // the ids of Txns built by hand need not be ascending
+    for tids in item_tids.values_mut() {
+        tids.sort_unstable();
+        tids.dedup();
+    }
+
+    let positions: CountMap<Vec<I>, usize> = fre_sets.iter().enumerate().map(|(i, x)| (x.items.clone(), i)).collect();
+
+    for i in 0..fre_sets.len() {
+        let degree = fre_sets[i].degree;
+
+        fre_sets[i].tids = if degree == 1 {
+            item_tids.get(&fre_sets[i].items[0]).cloned().unwrap_or_default()
+        } else {
+            let a = positions.get(&without_item(&fre_sets[i].items, degree - 1));
+            let b = positions.get(&without_item(&fre_sets[i].items, degree - 2));
+            match (a, b) {
+                (Some(&a), Some(&b)) if a < i && b < i => intersect_sorted(&fre_sets[a].tids, &fre_sets[b].tids),
+                _ => scan_tids(&fre_sets[i].items, txn_set),
+            }
+        };
+    }
+}
+
+/// the sorted ids of the txns of txn_set holding every item of items
+fn scan_tids<I: Item>(items: &[I], txn_set: &[Txn<I>]) -> Vec<usize> {
+    let mut tids: Vec<usize> = txn_set.iter().filter(|x| items.iter().all(|item| x.items.contains(item))).map(|x| x.id).collect();
+    tids.sort_unstable();
+    tids.dedup();
+    tids
+}
+
+/// # a frequent itemset of eclat with its tidset, the positions of the txns containing it
+struct EclatNode<I> {
+    items: Vec<I>,
+    tids: Vec<usize>,
+    count: usize,
+}
+
+/// ## push the itemsets of class and all their extensions to fre_sets
+///
+/// the itemsets of class are frequent and share all items but their last, joining each with the ones after it
+/// gives the frequent itemsets one item larger starting with it, the next class, mined depth first
+fn eclat_class<I: Item>(class: &[EclatNode<I>], txn_set: &[Txn<I>], min_sup: &MinSupport, fre_sets: &mut Vec<FrequentSet<I>>) {
+
+    for (i, node) in class.iter().enumerate() {
+        let mut next_class: Vec<EclatNode<I>> = Vec::new();
+
+        for other in class[i + 1..].iter() {
+            let tids = intersect_sorted(&node.tids, &other.tids);
+            let count = tidset_weight(&tids, txn_set);
+
+            if min_sup.is_frequent(count, 0.0) {
+                let mut items = node.items.clone();
+                items.push(other.items[other.items.len() - 1].clone());
+                next_class.push(EclatNode { items, tids, count });
+            }
+        }
+
+        fre_sets.push(FrequentSet {
+            degree: node.items.len(),
+            items: node.items.clone(),
+            count: node.count,
+            weighted_count: node.count as f64,
+            tids: Vec::new(),
+        });
+
+        eclat_class(&next_class, txn_set, min_sup, fre_sets);
+    }
+}
+
+/// the number of original transactions in the tidset, the sum of the weights of its txns
+fn tidset_weight<I: Item>(tids: &[usize], txn_set: &[Txn<I>]) -> usize {
+    tids.iter().map(|&pos| txn_set[pos].weight).sum()
+}
+
+/// the ids in both a and b, both sorted
+fn intersect_sorted(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut both = Vec::new();
+    let (mut i, mut j) = (0, 0);
+
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                both.push(a[i]);
+                i += 1;
+                j += 1;
+            },
+        }
+    }
+
+    both
+}
+
+/// ## the ItemsetTrace of items after mining
+///
+/// every item is a 1-CandicateSet, so a 1-itemset always has a candidate_count
+fn trace_itemset<I: Item>(items: &[I], txn_set: &[Txn<I>], fre_sets: &[FrequentSet<I>], min_sup: &MinSupport, traced_count: Option<usize>) -> ItemsetTrace<I> {
+
+    let index = support_index(fre_sets);
+    let count = count_containing(items, txn_set);
+
+    let infrequent_subsets: Vec<Vec<I>> = if items.len() < 2 {
+        Vec::new()
+    } else {
+        (0..items.len()).map(|pos| without_item(items, pos)).filter(|x| !index.contains_key(x.as_slice())).collect()
+    };
+
+    let mut splits: Vec<(Vec<I>, Vec<I>, f64)> = Vec::new();
+    if let Some(fre_set) = index.get(items) {
+        // the bitmap is one bool per item, so no itemset overflows an integer mask
+        let mut bitmap = vec![false; items.len()];
+        while next_bitmap(&mut bitmap) {
+            let from: Vec<I> = items.iter().zip(&bitmap).filter(|x| *x.1).map(|x| x.0.clone()).collect();
+            let to: Vec<I> = items.iter().zip(&bitmap).filter(|x| !*x.1).map(|x| x.0.clone()).collect();
+
+            let conf = fre_set.weighted_count / index[from.as_slice()].weighted_count;
+            splits.push((from, to, conf));
+        }
+    }
+
+    ItemsetTrace {
+        items: items.to_vec(),
+        count,
+        min_count: min_sup.min_count,
+        candidate_count: if items.len() == 1 { Some(count) } else { traced_count },
+        infrequent_subsets,
+        frequent: index.contains_key(items),
+        splits,
+    }
+}
+
+/// generate 1-CandicateSet and thus 1-FrequentSet and add it in to the frequent sets
+///
+/// return the number of 1-CandicateSets, or fail with AprioriError::TooManyDistinctItems if C_1 has more items than max_distinct_items
+fn init_fre_set<I: Item>(txn_set: &mut [Txn<I>], weights: &[f64], min_sup: &MinSupport, max_distinct_items: Option<usize>, fre_sets: &mut Vec<FrequentSet<I>>) -> Result<usize, AprioriError> {
+    // generate C_1
+    let candicate_set_1 = create_candicate_set_1(txn_set, weights);
+
+    // stop before mining the wrong column
+    if let Some(max_distinct_items) = max_distinct_items {
+        if candicate_set_1.len() > max_distinct_items {
+            return Err(AprioriError::TooManyDistinctItems {
+                distinct_items: candicate_set_1.len(),
+                max_distinct_items,
+            });
+        }
+    }
+    let candi_num = candicate_set_1.len();
+
+    // generate F_1
+    let mut frequent_set_1: Vec<(I, (usize, f64))> = create_frequent_set_1(candicate_set_1, min_sup).into_iter().collect();
+
+    // sort F_1 in lexicographic order, so the items of every FrequentSet joined from it are sorted too
+    frequent_set_1.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // add F_1 to fre_set
+    for set in frequent_set_1.iter() {
+        let fre_set = FrequentSet {
+            degree: 1,
+            items: vec![set.0.clone()],
+            count: set.1.0,
+            weighted_count: set.1.1,
+            tids: Vec::new(),
+        };
+
+        fre_sets.push(fre_set);
+    }
+
+    Ok(candi_num)
+}
+
+/// ## compare the number of distinct items with the number of transactions
+///
+/// return Warning::HighItemCardinality if distinct_items > max_item_ratio * txn_count
+fn check_item_cardinality<I: Item>(txn_set: &[Txn<I>], max_item_ratio: f64) -> Option<Warning> {
+
+    let distinct_items = txn_set.iter().flat_map(|x| x.items.iter()).collect::<CountSet<&I>>().len();
+
+    let txn_num = txn_count(txn_set);
+
+    if distinct_items as f64 > max_item_ratio * txn_num as f64 {
+        return Some(Warning::HighItemCardinality {
+            distinct_items,
+            txn_count: txn_num,
+        });
+    }
+
+    None
+}
+
+/// ## the weight of each transaction when counting support
+///
+/// without time decay every transaction weighs 1
+///
+/// with a half-life of h days, lambda = ln(2) / h, and a transaction `age` days older than
+/// the newest timestamped one weighs exp(-lambda * age)
+///
+/// transactions without timestamp weigh 1
+///
+/// a collapsed Txn weighs that times its weight, as the transactions it stands for would together
+fn txn_weights<I: Item>(txn_set: &[Txn<I>], time_decay: Option<f64>) -> Vec<f64> {
+
+    let half_life = match time_decay {
+        Some(half_life) => half_life,
+        None => return txn_set.iter().map(|x| x.weight as f64).collect(),
+    };
+
+    let lambda = std::f64::consts::LN_2 / half_life;
+    let newest = txn_set.iter().filter_map(|x| x.timestamp).max().unwrap_or(0);
+
+    txn_set.iter().map(|x| match x.timestamp {
+        Some(timestamp) => {
+            let age = newest.saturating_sub(timestamp) as f64 / SECONDS_PER_DAY;
+            (-lambda * age).exp() * x.weight as f64
+        },
+        None => x.weight as f64,
+    }).collect()
+}
+
+/// a small seeded pseudo-random generator, splitmix64, good enough for sampling
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// uniform in 0..n, n > 0
+    fn below(&mut self, n: usize) -> usize {
+        ((self.next_u64() as u128 * n as u128) >> 64) as usize
+    }
+}
+
+/// the number of original transactions, the sum of their weights
+fn txn_count<I: Item>(txn_set: &[Txn<I>]) -> usize {
+    txn_set.iter().map(|x| x.weight).sum()
+}
+
+/// the number of original transactions containing items
+fn count_containing<I: Item>(items: &[I], txn_set: &[Txn<I>]) -> usize {
+    txn_set.iter().filter(|x| subset_of(items, &x.items)).map(|x| x.weight).sum()
+}
+
+/// ## merge the transactions with the same items and timestamp into one Txn, adding up their weights
+///
+/// the merged Txn keeps the id of the first of them, and the order of first appearance is kept
+fn collapse_duplicates<I: Item>(txn_set: Vec<Txn<I>>) -> Vec<Txn<I>> {
+
+    let mut positions: HashMap<(Vec<I>, Option<u64>), usize> = HashMap::new();
+    let mut collapsed: Vec<Txn<I>> = Vec::new();
+
+    for txn in txn_set {
+        match positions.get(&(txn.items.clone(), txn.timestamp)) {
+            Some(&pos) => collapsed[pos].weight += txn.weight,
+            None => {
+                positions.insert((txn.items.clone(), txn.timestamp), collapsed.len());
+                collapsed.push(txn);
+            },
+        }
+    }
+
+    collapsed
+}
+
+/// the one-word bloom filter of a set of items, one bit per item chosen by its hash
+///
+/// DefaultHasher::new() has fixed keys, so txns and candidates get the same bits
+fn item_signature<I: Item>(items: &[I]) -> u64 {
+    use std::hash::Hasher;
+
+    items.iter().fold(0, |signature, item| {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        item.hash(&mut hasher);
+        signature | 1 << (hasher.finish() % 64)
+    })
+}
+
+/// ## judge whether a set is a subset of another set
+///
+/// both have to be sorted, like the items of every Txn, CandicateSet and FrequentSet,
+/// so one walk over set finds the items of subset in order, O(n + m) instead of a scan per item,
+/// a repeated item in subset needs to be repeated as often in set
+///
+/// the walk only tests equality, on short transactions of String items that beats stopping
+/// at the first greater item, which costs a full comparison per step
+fn subset_of<I: Item>(subset: &[I], set: &[I]) -> bool {
+    if subset.len() > set.len() {
+        return false;
+    }
+
+    let mut rest = set.iter();
+
+    // every item is searched after the one before, so each item of set is compared once at most
+    subset.iter().all(|item| rest.any(|x| x == item))
+}
+
+/// generate set of K-CandicateSet from set of (K-1)-FrequentSet
+/// 
+/// if two (K-1)-FrequentSets, the first K-2 elements are identical and the (k-1)th are different
+/// 
+/// then generate new CandicateSet with (degree - 1) elements and the degree-th element 
+///
+/// and prune it unless all its subsets one item smaller are frequent, the Apriori property
+fn get_candi_from_f<I: Item>(fre_sets: &[FrequentSet<I>], degree: usize) -> Vec<CandicateSet<I>> {
+
+    let mut candi:Vec<CandicateSet<I>> = Vec::new();
+
+    // fre_sets is a set of frequent_set with all kinds of degrees
+    // get degree-frequent_sets which is a set of frequent_set with degree items
+    let degree_fre_sets = get_degree_fre_sets(fre_sets, degree);
+    // a join needs two FrequentSets
+    if degree_fre_sets.len() < 2 {
+        return candi;
+    }
+
+    let frequent: CountSet<&[I]> = degree_fre_sets.iter().map(|x| x.items.as_slice()).collect();
+
+    for i in 0..(degree_fre_sets.len() - 1) {
+        for j in i+1..degree_fre_sets.len() {
+            if let Some(new_candi) = join_fre_sets(degree_fre_sets[i], degree_fre_sets[j], degree) {
+                if all_subsets_frequent(&new_candi.items, |x| frequent.contains(x)) {
+                    candi.push(new_candi);
+                }
+            }
+        }
+    }
+
+    candi
+}
+
+/// ## the prune step, whether every subset of items one item smaller is frequent
+///
+/// items is joined from two FrequentSets which are the subsets without one of the last two items,
+/// so only the others have to be looked up
+fn all_subsets_frequent<I: Item, F: Fn(&[I]) -> bool>(items: &[I], is_frequent: F) -> bool {
+    (0..items.len().saturating_sub(2)).all(|skip| is_frequent(&without_item(items, skip)))
+}
+
+/// ## join two degree-FrequentSets into a (degree + 1)-CandicateSet
+///
+/// if the first (degree -1) elements are identical and the degree-th elements are different
+fn join_fre_sets<I: Item>(a: &FrequentSet<I>, b: &FrequentSet<I>, degree: usize) -> Option<CandicateSet<I>> {
+
+    // the first (degree -1) elements are identical
+    if a.items.as_slice()[0..(degree-1)] != b.items.as_slice()[0..(degree-1)] {
+        return None;
+    }
+
+    // the degree-th elements are different
+    if a.items.get(degree - 1).unwrap() == b.items.get(degree - 1).unwrap() {
+        return None;
+    }
+
+    // then generate new CandicateSet with (degree - 1) elements and the degree-th element
+    // generate items
+    let mut items = a.items.clone();
+    items.push(b.items.get(degree - 1).unwrap().clone());
+
+    Some(CandicateSet {
+        degree: degree + 1,
+        items,
+        count: 0,
+        weighted_count: 0.0,
+    })
+}
+
+/// # the same CandicateSets as get_candi_from_f, generated lazily at most batch_size at a time
+///
+/// counting can go on batch by batch, so only batch_size CandicateSets are in memory at once
+///
+/// the FrequentSets of degree are copied, since the new FrequentSets are pushed to fre_sets while the batches are counted
+struct CandidateBatches<I> {
+    degree_fre_sets: Vec<FrequentSet<I>>,
+    frequent: CountSet<Vec<I>>,
+    degree: usize,
+    batch_size: usize,
+    i: usize,
+    j: usize,
+}
+
+impl<I: Item> CandidateBatches<I> {
+    fn new(fre_sets: &[FrequentSet<I>], degree: usize, batch_size: usize) -> CandidateBatches<I> {
+        let degree_fre_sets: Vec<FrequentSet<I>> = get_degree_fre_sets(fre_sets, degree).into_iter().cloned().collect();
+
+        CandidateBatches {
+            frequent: degree_fre_sets.iter().map(|x| x.items.clone()).collect(),
+            degree_fre_sets,
+            degree,
+            batch_size: batch_size.max(1),
+            i: 0,
+            j: 1,
+        }
+    }
+}
+
+impl<I: Item> Iterator for CandidateBatches<I> {
+    type Item = Vec<CandicateSet<I>>;
+
+    fn next(&mut self) -> Option<Vec<CandicateSet<I>>> {
+
+        let len = self.degree_fre_sets.len();
+        let mut batch: Vec<CandicateSet<I>> = Vec::new();
+
+        // walk the same (i, j) pairs as get_candi_from_f, resuming where the last batch stopped
+        while self.i + 1 < len && batch.len() < self.batch_size {
+            if self.j >= len {
+                self.i += 1;
+                self.j = self.i + 1;
+                continue;
+            }
+
+            if let Some(new_candi) = join_fre_sets(&self.degree_fre_sets[self.i], &self.degree_fre_sets[self.j], self.degree) {
+                if all_subsets_frequent(&new_candi.items, |x| self.frequent.contains(x)) {
+                    batch.push(new_candi);
+                }
+            }
+
+            self.j += 1;
+        }
+
+        if batch.is_empty() {
+            None
+        } else {
+            Some(batch)
+        }
+    }
+}
+
+/// fre_sets is a set of frequent_set with all kinds of degrees
+/// 
+/// get degree-frequent_sets which is a set of frequent_set with degree items,
+/// borrowed from fre_sets rather than copied
+fn get_degree_fre_sets<I: Item>(fre_sets: &[FrequentSet<I>], degree: usize) -> Vec<&FrequentSet<I>> {
+    fre_sets.iter().filter(|&x| x.degree == degree).collect()
+}
+
+/// ## get len of f based on the degree
+/// 
+/// the fre_sets is a set of all FrequentSet, degree indicates the number of item in each FrequentSet
+fn len_of_f_degree<I: Item>(fre_sets: &[FrequentSet<I>] , degree: usize) -> usize {
+    fre_sets.iter().filter(|x| x.degree == degree).count()
+}
+
+/// ## generate frequent_set_1, given clone of candicate_set_1 and min_sup
+/// 
+/// if candicate_set's count reaches min_count, the same bar as every other degree, the set is frequent
+/// 
+/// under time decay, the weighted count is compared with total_weight * min_sup instead
+///
+/// to avoid changing the value in candicate_set_1, use clone of it
+fn create_frequent_set_1<I: Item>(candicate_set_1: CountMap<I, (usize, f64)>, min_sup: &MinSupport) -> CountMap<I, (usize, f64)> {
+    let frequent_set_1: CountMap<I, (usize, f64)>
+        = candicate_set_1.into_iter()
+            .filter(|x| min_sup.is_frequent(x.1.0, x.1.1))
+            .collect::<CountMap<_,_>>();
+
+    frequent_set_1
+}
+
+/// ## generate candicate_set_1 from txn_set
+/// 
+/// candicate_set_1 is a hashmap which contains only one item(String) and its corresponding count and weighted count
+/// 
+/// txn_set: Vec of Txn
+/// 
+/// weights: the weight of each Txn
+///
+/// By using hashMap, we can create candicate_set_1 conveniently
+fn create_candicate_set_1<I: Item>(txn_set: &[Txn<I>], weights: &[f64]) -> CountMap<I, (usize, f64)> {
+    
+    // generate C_1
+    let mut candicate_set_1: CountMap<I, (usize, f64)> = CountMap::default();
+    for (txn, weight) in txn_set.iter().zip(weights) {
+
+        for item in txn.items.clone() {
+            candicate_set_1.entry(item).and_modify(|x| { x.0 += txn.weight; x.1 += weight; }).or_insert((txn.weight, *weight));
+        }
+    }
+
+    candicate_set_1
+}
+
+/// ## generate txn_set from csv file, the items in each txn are sorted in lexicographic order
+/// 
+/// filename: the path and name of the dataset.csv
+///
+/// config: how the csv file is formatted, see read_txns
+fn create_sorted_txn_set(filename: &str, config: &AprioriConfig) -> Result<Vec<Txn>, AprioriError> {
+    read_txns(filename, config)?.collect()
+}
+
+/// ## stream the transactions of a csv file one by one
+///
+/// each record is one transaction, sorted by Txn::new, nothing but the current record is held in memory
+///
+/// opening the file fails right away, a malformed record fails when it is reached
+fn read_txns(filename: &str, config: &AprioriConfig) -> Result<impl Iterator<Item = Result<Txn, AprioriError>>, AprioriError> {
+    Ok(csv_txns(std::fs::File::open(filename)?, config))
+}
+
+/// ## stream the transactions of csv data from reader, see read_txns
+///
+/// the first record is skipped as a header unless config.has_headers is off,
+/// the fields are split at config.delimiter and records may have any number of them
+///
+/// every field is trimmed, so " milk" and "milk" are one item, then mapped through config.normalize_item if set,
+/// and fields empty after that are no items
+fn csv_txns<R: std::io::Read>(reader: R, config: &AprioriConfig) -> impl Iterator<Item = Result<Txn, AprioriError>> {
+
+    let reader = csv::ReaderBuilder::new()
+        .has_headers(config.has_headers)
+        .delimiter(config.delimiter)
+        // transactions have different lengths, unless padded with empty fields like groceries.csv
+        .flexible(true)
+        .from_reader(reader);
+    let normalize_item = config.normalize_item.clone();
+
+    reader.into_records().enumerate().map(move |(i, items_result)| {
+        let items = items_result?;
+
+        let items_vec: Vec<String> = items.iter()
+            .map(|x| x.trim())
+            .map(|x| match &normalize_item {
+                Some(ItemNormalizer(normalize)) => normalize(x),
+                None => x.to_string(),
+            })
+            .filter(|x| !x.is_empty())
+            .collect();
+
+        // sort the items for each txn in txn_set in lexicographic order
+        Ok(Txn::new(i, items_vec))
+    })
+}
+
+/// get good filename based on min_sup and min_conf
+pub fn get_good_filename(min_sup: f64, min_conf: f64) -> String {
+    let mut filename: String = "associationRule_".to_string();
+    filename += &min_sup.to_string();
+    filename += "_";
+    filename += &min_conf.to_string();
+    filename += ".txt";
+
+    filename
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "serde")]
+    use super::json::{JsonError, JsonReader};
+
+    /// groceries.csv, the dataset of the repository
+    const GROCERIES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/groceries.csv");
+
+    /// write content to a file of the temp dir unique to the test, return its path
+    fn temp_file(name: &str, content: &str) -> String {
+        let path = std::env::temp_dir().join(format!("apriori_test_{}_{}", std::process::id(), name));
+        std::fs::write(&path, content).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+
+    /// the items and count of every FrequentSet, in order
+    fn counts<I: Item>(fre_sets: &[FrequentSet<I>]) -> Vec<(Vec<I>, usize)> {
+        fre_sets.iter().map(|x| (x.items.clone(), x.count)).collect()
+    }
+
+    #[test]
+    fn partitioned_finds_the_same_fre_sets_as_the_whole_file() {
+        let (fre_sets, rules) = apriori(0.02, 0.2, GROCERIES).unwrap();
+
+        for num_partitions in [1, 4] {
+            let (partitioned_sets, partitioned_rules) = apriori_partitioned(0.02, 0.2, GROCERIES, num_partitions).unwrap();
+            assert_eq!(counts(&partitioned_sets), counts(&fre_sets));
+            assert_eq!(partitioned_rules, rules);
+        }
+    }
+
+    #[test]
+    fn partitioned_rejects_partitions_below_one_over_min_sup() {
+        // 9835 transactions in 30 partitions of at most 328, 328 * 0.003 < 1
+        let err = apriori_partitioned(0.003, 0.2, GROCERIES, 30).unwrap_err();
+        assert!(matches!(err, AprioriError::TooManyPartitions { num_partitions: 30, .. }));
+
+        // 10 transactions of 4, 4, 2, the remainder is below 1 / 0.4
+        let filename = temp_file("remainder.csv", "h\na\na\na\na\na\na\na\na\na\na\n");
+        let err = apriori_partitioned(0.4, 0.5, &filename, 3).unwrap_err();
+        assert!(matches!(err, AprioriError::TooManyPartitions { smallest_partition: 2, .. }));
+        assert!(apriori_partitioned(0.5, 0.5, &filename, 3).is_ok());
+    }
+
+    /// a FrequentSet of items with count and no tids
+    fn fre_set(items: &[&str], count: usize) -> FrequentSet {
+        let items: Vec<String> = items.iter().map(|x| x.to_string()).collect();
+        FrequentSet { degree: items.len(), items, count, weighted_count: count as f64, tids: Vec::new() }
+    }
+
+    /// every rule for_each_rule generates from fre_sets at min_conf, and every ProgressEvent reported on the way
+    fn rules_and_events(fre_sets: &[FrequentSet], min_conf: f64, txn_num: usize) -> (Vec<AssociationRule>, Vec<ProgressEvent>) {
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = events.clone();
+        let config = AprioriConfig::new().min_confidence(min_conf).progress(move |event| reported.lock().unwrap().push(event));
+
+        let mut rules = Vec::new();
+        for_each_rule(fre_sets, fre_sets, &config, txn_num as f64, &mut |rule| {
+            rules.push(rule);
+            ControlFlow::Continue(())
+        });
+
+        let events = events.lock().unwrap().clone();
+        (rules, events)
+    }
+
+    #[test]
+    fn inconsistent_fre_sets_skip_the_split_instead_of_panicking() {
+        // {b} is missing, so neither b -> a nor a -> b can be looked up
+        let (rules, events) = rules_and_events(&[fre_set(&["a"], 2), fre_set(&["a", "b"], 2)], 0.5, 4);
+        assert!(rules.is_empty());
+        assert_eq!(events, vec![ProgressEvent::MissingSubset { degree: 2 }; 2]);
+
+        // {a} has no support, a -> b would divide by zero, b -> a is fine
+        let (rules, events) = rules_and_events(&[fre_set(&["a"], 0), fre_set(&["b"], 2), fre_set(&["a", "b"], 1)], 0.5, 4);
+        assert_eq!(rules.len(), 1);
+        assert_eq!((rules[0].from(), rules[0].confidence()), (&["b".to_string()][..], 0.5));
+        assert_eq!(events, vec![ProgressEvent::ZeroAntecedentSupport { degree: 2 }]);
+
+        assert!(top_rules(&[fre_set(&["a"], 2), fre_set(&["a", "b"], 2)], 0.5, 4, 10).is_empty());
+    }
+
+    /// transactions of the given items, with ids in order
+    fn txns(items: &[&[&str]]) -> Vec<Txn> {
+        items.iter().enumerate().map(|(i, x)| Txn::new(i, x.iter().map(|x| x.to_string()).collect())).collect()
+    }
+
+    /// owned Strings from strs
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn required_and_forbidden_items_filter_the_fre_sets() {
+        let txn_set = txns(&[&["a", "b", "c"], &["a", "b", "c"], &["b", "c"], &["a", "d"], &["a", "d"]]);
+        let result = AprioriConfig::new()
+            .min_support(0.4)
+            .min_confidence(0.5)
+            .required_items(strings(&["a"]))
+            .forbidden_items(strings(&["d"]))
+            .run_on_txn_set(txn_set)
+            .unwrap();
+
+        let expected = vec![
+            (strings(&["a"]), 4),
+            (strings(&["a", "b"]), 2),
+            (strings(&["a", "c"]), 2),
+            (strings(&["a", "b", "c"]), 2),
+        ];
+        assert_eq!(counts(&result.fre_sets), expected);
+
+        // the rules were generated with the dropped subsets still there, b -> a needs the support of {b}
+        assert!(result.association_rules_set.iter().any(|x| x.from() == strings(&["b"]) && x.to() == strings(&["a"])));
+        assert!(result.association_rules_set.iter().all(|x| !x.from().contains(&"d".to_string()) && !x.to().contains(&"d".to_string())));
+    }
+
+    #[test]
+    fn helpers_skip_what_required_items_dropped() {
+        let txn_set = txns(&[&["a", "b", "c"], &["a", "b", "c"], &["b", "c"]]);
+        let result = AprioriConfig::new().min_support(0.5).min_confidence(0.1).required_items(strings(&["a"])).run_on_txn_set(txn_set).unwrap();
+        let fre_sets = &result.fre_sets;
+
+        // every rule of the partial lattice needs {b}, {c} or {b, c}, which are gone
+        assert!(top_rules(fre_sets, 0.1, 3, 10).is_empty());
+        assert!(rules_for_confidences(fre_sets, &[0.1, 0.5], 3).values().all(|x| x.is_empty()));
+        let (sender, receiver) = std::sync::mpsc::sync_channel(4);
+        generate_rules_to_channel(fre_sets, 0.1, 3, sender);
+        assert_eq!(receiver.iter().count(), 0);
+
+        assert!(item_cosine_similarity(fre_sets, 10).is_empty());
+        assert!(confidence_asymmetry(fre_sets, 3).is_empty());
+        non_redundant_rules(fre_sets, 0.1, 3);
+    }
+
+    #[test]
+    fn estimate_complexity_tells_easy_from_hard() {
+        let easy = txns(&[&["a", "b"], &["a", "c"], &["b", "c"], &["a", "b", "c"]]);
+        let estimate = estimate_complexity(&easy, 0.5);
+        assert_eq!(estimate.frequent_items, 3);
+        assert_eq!(estimate.class, RuntimeClass::Quick);
+
+        // 40 items in every transaction, every one of the 2^40 itemsets is frequent, counted without enumerating them
+        let items: Vec<String> = (0..40).map(|x| format!("item{}", x)).collect();
+        let dense: Vec<Txn> = (0..10).map(|i| Txn::new(i, items.clone())).collect();
+        let estimate = estimate_complexity(&dense, 0.5);
+        assert_eq!(estimate.max_degree, 40);
+        assert!((estimate.estimated_candidates / 2_f64.powi(40) - 1.0).abs() < 1e-6);
+        assert_eq!(estimate.class, RuntimeClass::LikelyInfeasible);
+    }
+
+    #[test]
+    fn long_csv_has_one_row_per_item() {
+        let txn_set = txns(&[&["a", "b", "c"], &["a", "b"], &["a", "c"], &["b"]]);
+        let result = AprioriConfig::new().min_support(0.5).min_confidence(0.5).run_on_txn_set(txn_set).unwrap();
+
+        let sets_file = temp_file("long_sets.csv", "");
+        write_fre_sets_long_csv(&sets_file, &result.fre_sets, 4).unwrap();
+        let set_items: usize = result.fre_sets.iter().map(|x| x.items.len()).sum();
+        assert_eq!(std::fs::read_to_string(&sets_file).unwrap().lines().count(), set_items + 1);
+
+        let rules_file = temp_file("long_rules.csv", "");
+        write_rules_long_csv(&rules_file, &result.association_rules_set).unwrap();
+        let rule_items: usize = result.association_rules_set.iter().map(|x| x.from.len() + x.to.len()).sum();
+        assert!(rule_items > 0);
+        assert_eq!(std::fs::read_to_string(&rules_file).unwrap().lines().count(), rule_items + 1);
+
+        // a directory can't be written as a file
+        let dir = std::env::temp_dir();
+        assert!(matches!(write_fre_sets_long_csv(dir.to_str().unwrap(), &result.fre_sets, 4), Err(AprioriError::Io(_))));
+        assert!(matches!(write_rules_long_csv(dir.to_str().unwrap(), &result.association_rules_set), Err(AprioriError::Io(_))));
+    }
+
+    #[test]
+    fn rules_from_itemsets_scores_curated_itemsets() {
+        let txn_set = txns(&[&["a", "b", "c"], &["a", "b"], &["a", "c"], &["b", "d"]]);
+
+        // given unsorted and twice, {a, b} gives a -> b (2 / 3) and b -> a (2 / 3)
+        let itemsets = vec![strings(&["b", "a"]), strings(&["a", "b"]), strings(&["c", "d"])];
+        let mut rules = rules_from_itemsets(&itemsets, &txn_set, 0.5);
+        sort_rules_by_confidence(&mut rules);
+
+        let splits: Vec<(&[String], &[String])> = rules.iter().map(|x| (x.from(), x.to())).collect();
+        assert_eq!(splits, vec![(&strings(&["a"])[..], &strings(&["b"])[..]), (&strings(&["b"])[..], &strings(&["a"])[..])]);
+        assert!(rules.iter().all(|x| x.union_count() == 2 && (x.confidence() - 2.0 / 3.0).abs() < 1e-12));
+
+        // an itemset which never occurs gives no rule, whatever its subsets
+        assert!(rules_from_itemsets(&[strings(&["a", "b", "c", "d", "e"])], &txn_set, 0.5).is_empty());
+    }
+
+    #[test]
+    fn trace_itemset_explains_a_narrow_miss() {
+        let txn_set = || txns(&[&["a", "b"], &["a", "b"], &["a"], &["b"], &["c"], &["c"]]);
+        let config = AprioriConfig::new().min_confidence(0.5).trace_itemset(strings(&["b", "a"]));
+
+        // {a, b} is in 2 transactions, one short of min_count 3
+        let trace = config.clone().min_support(0.5).run_on_txn_set(txn_set()).unwrap().trace.unwrap();
+        assert_eq!(trace.items, strings(&["a", "b"]));
+        assert_eq!((trace.count, trace.min_count, trace.candidate_count), (2, 3, Some(2)));
+        assert!(trace.infrequent_subsets.is_empty());
+        assert!(!trace.frequent);
+        assert!(trace.splits.is_empty());
+
+        // at min_count 2 it is frequent, with both splits at 2 / 3
+        let trace = config.min_support_count(2).run_on_txn_set(txn_set()).unwrap().trace.unwrap();
+        assert!(trace.frequent);
+        assert_eq!(trace.splits, vec![
+            (strings(&["a"]), strings(&["b"]), 2.0 / 3.0),
+            (strings(&["b"]), strings(&["a"]), 2.0 / 3.0),
+        ]);
+    }
+
+    /// an item type without FromStr
+    #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    struct Sku(u32);
+
+    #[test]
+    fn items_need_not_parse_from_str() {
+        let transactions = vec![vec![Sku(1), Sku(2)], vec![Sku(1), Sku(2)], vec![Sku(1)]];
+        let result = AprioriConfig::<Sku>::default().min_support(0.5).run_on_transactions(transactions).unwrap();
+
+        assert_eq!(counts(&result.fre_sets), vec![(vec![Sku(1)], 3), (vec![Sku(2)], 2), (vec![Sku(1), Sku(2)], 2)]);
+    }
+
+    #[test]
+    fn ignore_items_file_parses_its_lines_as_items() {
+        let filename = temp_file("ignored_codes.txt", "2\n not a code \n\n");
+        let transactions = vec![vec![1_u32, 2], vec![1, 2], vec![1]];
+        let result = AprioriConfig::<u32>::default().min_support(0.5).ignore_items_file(&filename)
+            .run_on_transactions(transactions).unwrap();
+
+        assert_eq!(counts(&result.fre_sets), vec![(vec![1], 3)]);
+    }
+
+    #[test]
+    fn memory_counts_the_bytes_of_string_items() {
+        let transactions = || vec![strings(&["a long item name", "b"]), strings(&["a long item name", "b"])];
+        let owned = AprioriConfig::new().min_support(0.5).run_on_transactions(transactions()).unwrap().memory;
+        let buffers = AprioriConfig::<String>::default().min_support(0.5).run_on_transactions(transactions()).unwrap().memory;
+
+        assert!(owned.fre_sets_bytes > buffers.fre_sets_bytes);
+        assert!(owned.rules_bytes > buffers.rules_bytes);
+    }
+
+    #[test]
+    fn time_decay_weights_support_by_recency() {
+        const DAY: u64 = 86_400;
+        let txn_set = || vec![
+            Txn::with_timestamp(0, strings(&["a"]), 0),
+            Txn::with_timestamp(1, strings(&["a"]), 0),
+            Txn::with_timestamp(2, strings(&["a"]), 0),
+            Txn::with_timestamp(3, strings(&["b"]), 10 * DAY),
+            Txn::with_timestamp(4, strings(&["b"]), 10 * DAY),
+        ];
+        let config = AprioriConfig::new().min_support(0.6);
+
+        // undecayed, a is in 3 of 5 transactions
+        let undecayed = config.clone().run_on_txn_set(txn_set()).unwrap().fre_sets;
+        assert_eq!(counts(&undecayed), vec![(strings(&["a"]), 3)]);
+        assert_eq!(undecayed[0].weighted_count(), 3.0);
+
+        // with a half-life of a day, the a transactions are 10 half-lives old and weigh 2^-10 each
+        let decayed = config.time_decay(1.0).run_on_txn_set(txn_set()).unwrap().fre_sets;
+        assert_eq!(counts(&decayed), vec![(strings(&["b"]), 2)]);
+        assert!((decayed[0].weighted_count() - 2.0).abs() < 1e-9);
+    }
+
+    /// the rule from -> to of rules
+    fn rule<'a>(rules: &'a [AssociationRule], from: &[&str], to: &[&str]) -> &'a AssociationRule {
+        rules.iter().find(|x| x.from == strings(from) && x.to == strings(to)).unwrap()
+    }
+
+    #[test]
+    fn rule_support_overlap_is_the_jaccard_of_the_txns_fired_on() {
+        let txn_set = || txns(&[&["a", "b", "c"], &["a", "b"], &["b", "c"], &["a", "b", "c"], &["d"], &["a"]]);
+        let rules = AprioriConfig::new().min_support_count(2).min_confidence(0.1).run_on_txn_set(txn_set()).unwrap().association_rules_set;
+        let txn_set = txn_set();
+
+        // a -> b fires on 0, 1 and 3, b -> c on 0, 2 and 3
+        let a_b = rule(&rules, &["a"], &["b"]);
+        let b_c = rule(&rules, &["b"], &["c"]);
+        assert_eq!(rule_support_overlap(a_b, b_c, &txn_set), 0.5);
+        assert_eq!(rule_support_overlap(a_b, a_b, &txn_set), 1.0);
+        assert_eq!(rule_support_overlap(a_b, b_c, &txn_set[4..]), 0.0);
+    }
+
+    #[test]
+    fn ids_read_as_items_trigger_the_cardinality_warning() {
+        // the order ids were meant to be skipped, every one of them is a distinct item
+        let filename = temp_file("misparsed.csv", "order,item\n1001,milk\n1002,milk\n1003,bread\n");
+        let config = AprioriConfig::new().min_support(0.5);
+
+        let warnings = config.clone().run_on_file(&filename).unwrap().warnings;
+        assert!(warnings.contains(&Warning::HighItemCardinality { distinct_items: 5, txn_count: 3 }));
+
+        let warnings = config.item_cardinality_warning(2.0).run_on_file(&filename).unwrap().warnings;
+        assert!(!warnings.iter().any(|x| matches!(x, Warning::HighItemCardinality { .. })));
+    }
+
+    #[test]
+    fn contingency_table_matches_a_hand_count() {
+        let txn_set = || txns(&[&["a", "b", "c"], &["a", "b"], &["b", "c"], &["a", "b", "c"], &["d"], &["a"]]);
+        let rules = AprioriConfig::new().min_support_count(2).min_confidence(0.1).run_on_txn_set(txn_set()).unwrap().association_rules_set;
+
+        // a and b in 0, 1, 3, only a in 5, only b in 2, neither in 4
+        assert_eq!(contingency_table(rule(&rules, &["a"], &["b"]), &txn_set()), [3, 1, 1, 1]);
+        // b and c in 0, 2, 3, only b in 1, neither in 4, 5
+        assert_eq!(contingency_table(rule(&rules, &["c"], &["b"]), &txn_set()), [3, 0, 1, 2]);
+    }
+
+    #[test]
+    fn minimal_generators_have_no_subset_of_the_same_count() {
+        let txn_set = txns(&[&["a", "b"], &["a", "b"], &["a", "b", "c"], &["c"]]);
+        let result = AprioriConfig::new().min_support_count(1).run_on_txn_set(txn_set).unwrap();
+
+        // {a, b} has the count of {a} and {a, b, c} the count of {a, c}, both only close a generator
+        let expected = vec![
+            (strings(&["a"]), 3),
+            (strings(&["b"]), 3),
+            (strings(&["c"]), 2),
+            (strings(&["a", "c"]), 1),
+            (strings(&["b", "c"]), 1),
+        ];
+        assert_eq!(counts(&minimal_generators(&result.fre_sets)), expected);
+    }
+
+    #[test]
+    fn non_redundant_rules_are_a_lossless_subset_of_the_full_rules() {
+        let txn_set = || txns(&[&["a", "b"], &["a", "b"], &["a", "b", "c"], &["c"], &["a", "c"]]);
+        let config = AprioriConfig::new().min_support_count(1).min_confidence(0.3);
+        let full = config.clone().run_on_txn_set(txn_set()).unwrap();
+        let mut basis = non_redundant_rules(&full.fre_sets, 0.3, 5);
+        sort_rules_by_confidence(&mut basis);
+
+        assert!(!basis.is_empty() && basis.len() < full.association_rules_set.len());
+        assert!(basis.iter().all(|x| full.association_rules_set.contains(x)));
+
+        // the support and confidence of every rule are those of a basis rule over a superset of its items
+        for rule in full.association_rules_set.iter() {
+            let items: Vec<String> = rule.from.iter().chain(rule.to.iter()).cloned().collect();
+            assert!(basis.iter().any(|x| {
+                let basis_items: Vec<String> = x.from.iter().chain(x.to.iter()).cloned().collect();
+                items.iter().all(|item| basis_items.contains(item)) && x.sup == rule.sup && x.conf == rule.conf
+            }), "{:?} -> {:?} is lost", rule.from, rule.to);
+        }
+
+        // the config option gives the same basis
+        assert_eq!(config.non_redundant_rules(true).run_on_txn_set(txn_set()).unwrap().association_rules_set, basis);
+    }
+
+    #[test]
+    fn benchmark_file_only_returns_timings() {
+        let listing = || {
+            let mut files: Vec<_> = std::fs::read_dir(env!("CARGO_MANIFEST_DIR")).unwrap().map(|x| x.unwrap().file_name()).collect();
+            files.sort();
+            files
+        };
+        let before = listing();
+
+        let timings = AprioriConfig::new().benchmark_file(GROCERIES).unwrap();
+        assert!(timings.load > Duration::ZERO && timings.frequent_sets > Duration::ZERO);
+        assert_eq!(listing(), before);
+    }
+
+    #[test]
+    fn rules_of_a_high_degree_set_only_take_one_item_as_to() {
+        // 2^40 splits would never finish, above the soft limit of 20 there are only 40
+        let items: Vec<String> = (0..40).map(|x| format!("item{:02}", x)).collect();
+        let names: Vec<&str> = items.iter().map(|x| x.as_str()).collect();
+        let big = fre_set(&names, 2);
+        let mut fre_sets: Vec<FrequentSet> = (0..40).map(|x| fre_set(&[names[x]], 3)).collect();
+        fre_sets.extend((0..40).map(|skip| fre_set(&without_item(&names, skip), 2)));
+        fre_sets.push(big.clone());
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = events.clone();
+        assert_eq!(AprioriConfig::new().rule_degree_soft_limit, None);
+        let config = AprioriConfig::new().min_confidence(0.5).rule_degree_soft_limit(Some(20))
+            .progress(move |event| reported.lock().unwrap().push(event));
+        let mut rules = Vec::new();
+        for_each_rule([&big], &fre_sets, &config, 4.0, &mut |rule| {
+            rules.push(rule);
+            ControlFlow::Continue(())
+        });
+
+        assert_eq!(rules.len(), 40);
+        assert!(rules.iter().all(|x| x.from.len() == 39 && x.to.len() == 1 && x.conf == 1.0));
+        assert_eq!(*events.lock().unwrap(), vec![ProgressEvent::RuleDegreeSoftLimit { degree: 40 }]);
+    }
+
+    /// the 9 transactions of the textbook example, Han & Kamber, Data Mining: Concepts and Techniques
+    fn textbook() -> Vec<Txn> {
+        txns(&[
+            &["I1", "I2", "I5"], &["I2", "I4"], &["I2", "I3"],
+            &["I1", "I2", "I4"], &["I1", "I3"], &["I2", "I3"],
+            &["I1", "I3"], &["I1", "I2", "I3", "I5"], &["I1", "I2", "I3"],
+        ])
+    }
+
+    /// the textbook example mined at its min_count of 2
+    fn textbook_fre_sets() -> Vec<FrequentSet> {
+        AprioriConfig::new().min_support_count(2).run_on_txn_set(textbook()).unwrap().fre_sets
+    }
+
+    #[test]
+    fn item_cosine_similarity_of_the_textbook_pairs() {
+        let similarities = item_cosine_similarity(&textbook_fre_sets(), 4);
+        let expected = [
+            ("I1", "I3", 4.0 / 36_f64.sqrt()),
+            ("I1", "I2", 4.0 / 42_f64.sqrt()),
+            ("I2", "I3", 4.0 / 42_f64.sqrt()),
+            ("I1", "I5", 2.0 / 12_f64.sqrt()),
+        ];
+
+        assert_eq!(similarities.len(), expected.len());
+        for (similarity, (a, b, cosine)) in similarities.iter().zip(expected) {
+            assert_eq!((similarity.0.as_str(), similarity.1.as_str()), (a, b));
+            assert!((similarity.2 - cosine).abs() < 1e-12);
+        }
+
+        // counts of 2^40 have a product beyond usize
+        let huge = 1 << 40;
+        let similarities = item_cosine_similarity(&[fre_set(&["a"], huge), fre_set(&["b"], huge), fre_set(&["a", "b"], huge / 2)], 1);
+        assert_eq!(similarities, vec![("a".to_string(), "b".to_string(), 0.5)]);
+    }
+
+    #[test]
+    fn ignore_items_file_removes_its_items_before_mining() {
+        let data = temp_file("ignore_data.csv", "bag,milk,bread\nbag,milk\nbread,bag,eggs\n");
+        let ignored = temp_file("ignore_list.txt", "  bag \n\neggs\n");
+        let result = AprioriConfig::new().has_headers(false).min_support(0.6).ignore_items(strings(&["milk"]))
+            .ignore_items_file(&ignored).run_on_file(&data).unwrap();
+
+        // bag would be in every transaction, milk in 2 of 3
+        assert_eq!(counts(&result.fre_sets), vec![(strings(&["bread"]), 2)]);
+    }
+
+    #[test]
+    fn item_counts_ranks_every_item() {
+        let counts = item_counts(&textbook());
+        let expected: Vec<(String, usize)> = [("I2", 7), ("I1", 6), ("I3", 6), ("I4", 2), ("I5", 2)]
+            .iter().map(|&(item, count)| (item.to_string(), count)).collect();
+
+        assert_eq!(counts, expected);
+        assert_eq!(item_counts(&txns(&[&["rare"], &["common"], &["common"]]))[1], ("rare".to_string(), 1));
+    }
+
+    #[test]
+    fn groceries_gives_the_rules_of_the_repository_whatever_the_hasher() {
+        // associationRule_0.05_0.3.txt, written before the counting maps could be swapped by the fxhash feature
+        let expected = [
+            ("yogurt", 0.05602440264361973, 0.40160349854227406),
+            ("other vegetables", 0.07483477376715811, 0.38675775091960063),
+            ("rolls/buns", 0.05663446873411286, 0.30790491984521834),
+        ];
+        let (fre_sets, rules) = apriori(0.05, 0.3, GROCERIES).unwrap();
+
+        assert_eq!(fre_sets.len(), 31);
+        assert_eq!(rules.len(), expected.len());
+        for (rule, (from, sup, conf)) in rules.iter().zip(expected) {
+            assert_eq!((rule.from(), rule.to()), (&strings(&[from])[..], &strings(&["whole milk"])[..]));
+            assert!((rule.support() - sup).abs() < 1e-12 && (rule.confidence() - conf).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn candidate_batches_find_the_same_fre_sets_in_bounded_batches() {
+        let config = AprioriConfig::new().min_support(0.03).min_confidence(0.2);
+        let whole = config.clone().run_on_file(GROCERIES).unwrap();
+        let batched = config.candidate_batch_size(50).run_on_file(GROCERIES).unwrap();
+
+        assert_eq!(counts(&batched.fre_sets), counts(&whole.fre_sets));
+        assert_eq!(batched.association_rules_set, whole.association_rules_set);
+        assert_eq!(batched.candidate_counts, whole.candidate_counts);
+
+        // never more than 50 of the CandicateSets of degree 2 at once, the same ones in the same order
+        let candi_items = |candi_sets: Vec<CandicateSet>| candi_sets.into_iter().map(|x| x.items).collect::<Vec<_>>();
+        let batches: Vec<Vec<CandicateSet>> = CandidateBatches::new(&whole.fre_sets, 1, 50).collect();
+        assert!(batches.len() > 1 && batches.iter().all(|x| x.len() <= 50));
+        assert_eq!(candi_items(batches.into_iter().flatten().collect()), candi_items(get_candi_from_f(&whole.fre_sets, 1)));
+    }
+
+    #[test]
+    fn conditional_item_support_given_a_textbook_context() {
+        let fre_sets = textbook_fre_sets();
+        let owned = |supports: &[(&str, f64)]| supports.iter().map(|&(item, sup)| (item.to_string(), sup)).collect::<Vec<_>>();
+
+        // I1 is in 6 transactions, 4 of them with I2, 4 with I3 and 2 with I5
+        assert_eq!(conditional_item_support(&strings(&["I1"]), &fre_sets, 9), owned(&[("I2", 4.0 / 6.0), ("I3", 4.0 / 6.0), ("I5", 2.0 / 6.0)]));
+        assert_eq!(conditional_item_support(&strings(&["I2", "I1"]), &fre_sets, 9), owned(&[("I3", 0.5), ("I5", 0.5)]));
+        assert!(conditional_item_support(&strings(&["I4", "I5"]), &fre_sets, 9).is_empty());
+        assert_eq!(conditional_item_support(&[], &fre_sets, 9)[0], ("I2".to_string(), 7.0 / 9.0));
+    }
+
+    /// the rules of the textbook example at min_conf 0.5
+    fn textbook_rules() -> Vec<AssociationRule> {
+        AprioriConfig::new().min_support_count(2).min_confidence(0.5).run_on_txn_set(textbook()).unwrap().association_rules_set
+    }
+
+    #[test]
+    fn recommendations_for_a_known_basket() {
+        let index = build_recommendation_index(textbook_rules());
+
+        // I1 -> I2 and I1 -> I3 are both at 4 / 6, the lift of I3 is higher, I1 -> I5 is at 2 / 6
+        assert_eq!(index.recommend(&strings(&["I1"])), strings(&["I3", "I2"]));
+        // I5 -> I2 is exact, the basket items are never recommended
+        assert_eq!(index.recommend(&strings(&["I5", "I1"])), strings(&["I2", "I3"]));
+        assert!(index.recommend(&strings(&["I9"])).is_empty());
+    }
+
+    #[test]
+    fn max_distinct_items_fails_a_file_with_more_items() {
+        let filename = temp_file("distinct.csv", "a,b,c\nd,e\na,f\n");
+        let config = AprioriConfig::new().has_headers(false).min_support(0.5);
+
+        let err = config.clone().max_distinct_items(5).run_on_file(&filename).unwrap_err();
+        assert!(matches!(err, AprioriError::TooManyDistinctItems { distinct_items: 6, max_distinct_items: 5 }));
+        assert_eq!(err.to_string(), "6 distinct items, more than the maximum of 5");
+
+        assert!(config.max_distinct_items(6).run_on_file(&filename).is_ok());
+    }
+
+    #[test]
+    fn batch_support_matches_exact_support_of_each_query() {
+        let txn_set = textbook();
+        let queries = vec![strings(&["I1"]), strings(&["I3", "I1", "I2"]), strings(&["I4", "I5"]), strings(&["I2", "I2"]), strings(&["I9"]), vec![]];
+
+        let batched = batch_support(&queries, &txn_set);
+        let individual: Vec<Option<f64>> = queries.iter().map(|x| exact_support(x, &txn_set)).collect();
+        assert_eq!(batched, individual);
+        assert_eq!(batched[..3], [Some(6.0 / 9.0), Some(2.0 / 9.0), Some(0.0)]);
+
+        assert_eq!(batch_support(&queries, &[]), vec![None; queries.len()]);
+    }
+
+    #[cfg(feature = "binary")]
+    #[test]
+    fn binary_rules_read_back_equal_to_the_originals() {
+        let rules = textbook_rules();
+        assert!(rules.iter().any(|x| x.conviction == f64::INFINITY));
+
+        let filename = temp_file("rules.bin", "");
+        write_rules_bin(&filename, &rules).unwrap();
+        assert_eq!(read_rules_bin(&filename).unwrap(), rules);
+
+        // cut in the middle of the last rule
+        let buf = std::fs::read(&filename).unwrap();
+        let truncated = temp_file("truncated_rules.bin", "");
+        std::fs::write(&truncated, &buf[..buf.len() - 4]).unwrap();
+        assert_eq!(read_rules_bin(&truncated).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+
+        let not_rules = temp_file("not_rules.bin", "rule 0:\n");
+        assert_eq!(read_rules_bin(&not_rules).unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn explain_rule_formats_the_key_numbers() {
+        let rules = textbook_rules();
+
+        assert_eq!(explain_rule(rule(&rules, &["I1", "I5"], &["I2"])),
+            "Customers who buy I1 and I5 are 1.3× more likely to also buy I2 (100% of the time, based on 22.2% of baskets).");
+        assert_eq!(explain_rule(rule(&rules, &["I2"], &["I1"])),
+            "Customers who buy I2 are only 0.9× as likely to also buy I1 (57% of the time, based on 44.4% of baskets).");
+        assert_eq!(join_items(&strings(&["a", "b", "c"])), "a, b and c");
+    }
+
+    #[test]
+    fn membership_only_finds_the_sets_of_the_exact_run() {
+        let config = AprioriConfig::new().min_support(0.02);
+        let exact = config.clone().run_on_file(GROCERIES).unwrap().fre_sets;
+        let membership = config.membership_only(true).run_on_file(GROCERIES).unwrap().fre_sets;
+
+        let items = |fre_sets: &[FrequentSet]| fre_sets.iter().map(|x| x.items.clone()).collect::<Vec<_>>();
+        assert_eq!(items(&membership), items(&exact));
+
+        // counts are only stopped early above degree 1, and never below min_count
+        let min_count = (0.02 * 9835.0) as usize;
+        for (membership, exact) in membership.iter().zip(exact.iter()) {
+            assert!(membership.count >= min_count && membership.count <= exact.count);
+            if exact.degree == 1 {
+                assert_eq!(membership.count, exact.count);
+            }
+        }
+    }
+
+    #[test]
+    fn incremental_update_matches_a_full_re_mine() {
+        let all_txns = create_sorted_txn_set(GROCERIES, &AprioriConfig::new()).unwrap();
+        let (old_txns, new_txns) = all_txns.split_at(4000);
+        let mine = |txn_set: &[Txn]| {
+            let txn_set = txn_set.iter().map(|x| Txn::new(x.id, x.items.clone())).collect();
+            AprioriConfig::new().min_support(0.03).run_on_txn_set(txn_set).unwrap().fre_sets
+        };
+        let border_counts = |border: &NegativeBorder| border.sets().iter().map(|x| (x.items.clone(), x.count)).collect::<Vec<_>>();
+
+        let old_fre_sets = mine(old_txns);
+        let (fre_sets, border) = update_frequent_sets(&old_fre_sets, &negative_border(&old_fre_sets, old_txns), new_txns, &all_txns, 0.03);
+
+        let full_fre_sets = mine(&all_txns);
+        // some sets are only frequent in one of the two
+        let items = |fre_sets: &[FrequentSet]| fre_sets.iter().map(|x| x.items.clone()).collect::<Vec<_>>();
+        assert_ne!(items(&old_fre_sets), items(&full_fre_sets));
+        assert_eq!(counts(&fre_sets), counts(&full_fre_sets));
+        assert_eq!(border_counts(&border), border_counts(&negative_border(&full_fre_sets, &all_txns)));
+    }
+
+    #[test]
+    fn rule_score_orders_a_small_rule_set() {
+        let rules = textbook_rules();
+        let (exact, independent, negative) = (rule(&rules, &["I5"], &["I1"]), rule(&rules, &["I1"], &["I3"]), rule(&rules, &["I2"], &["I1"]));
+
+        // exact and lift 1.5 beats twice the support at 2 / 3 and lift 1, which beats 4 / 7 at lift 6 / 7
+        let score = |rule| rule_score(rule, ScoreWeights::default());
+        assert!(score(exact) > score(independent) && score(independent) > score(negative));
+        assert!((score(exact) - (0.5 * (2.0_f64 / 9.0).ln() + 1.5_f64.ln())).abs() < 1e-12);
+
+        // by support alone the exact rule, in 2 transactions, falls last
+        let by_support = |rule| rule_score(rule, ScoreWeights { support: 1.0, confidence: 0.0, lift: 0.0 });
+        assert!(by_support(exact) < by_support(independent) && by_support(independent) == by_support(negative));
+    }
+
+    #[test]
+    fn recommend_with_breaks_confidence_ties_stably() {
+        let mut rules = textbook_rules();
+        let index = build_recommendation_index(rules.clone());
+        rules.reverse();
+        let reversed = build_recommendation_index(rules);
+        let basket = strings(&["I1"]);
+
+        // I2 and I3 are both recommended at 4 / 6
+        for tie_break in [TieBreak::LiftThenSupport, TieBreak::Lexicographic] {
+            let options = RecommendOptions { tie_break, ..RecommendOptions::default() };
+            assert_eq!(index.recommend_with(&basket, options), reversed.recommend_with(&basket, options));
+        }
+        let lexicographic = RecommendOptions { tie_break: TieBreak::Lexicographic, ..RecommendOptions::default() };
+        assert_eq!(index.recommend_with(&basket, lexicographic), strings(&["I2", "I3"]));
+        assert_eq!(index.recommend_with(&basket, RecommendOptions::default()), strings(&["I3", "I2"]));
+
+        assert_eq!(index.recommend_with(&basket, RecommendOptions { top_n: Some(1), ..RecommendOptions::default() }), strings(&["I3"]));
+        assert!(index.recommend_with(&basket, RecommendOptions { min_confidence: 0.7, ..RecommendOptions::default() }).is_empty());
+    }
+
+    #[test]
+    fn rules_for_confidences_match_a_run_per_confidence() {
+        let config = AprioriConfig::new().min_support(0.03);
+        let fre_sets = config.clone().run_on_file(GROCERIES).unwrap().fre_sets;
+        let confidences = [0.3, 0.1, 0.2];
+        let rules_by_conf = rules_for_confidences(&fre_sets, &confidences, 9835);
+
+        assert_eq!(rules_by_conf.len(), confidences.len());
+        for min_conf in confidences {
+            let mut rules = rules_by_conf[&OrderedFloat(min_conf)].clone();
+            sort_rules_by_confidence(&mut rules);
+            assert_eq!(rules, config.clone().min_confidence(min_conf).run_on_file(GROCERIES).unwrap().association_rules_set);
+        }
+    }
+
+    #[test]
+    fn rule_counts_are_those_of_their_fre_sets() {
+        let fre_sets = textbook_fre_sets();
+        let count = |items: &[String]| fre_sets.iter().find(|x| x.items == items).unwrap().count;
+        let rules = textbook_rules();
+        assert!(!rules.is_empty());
+
+        for rule in rules.iter() {
+            let mut union: Vec<String> = rule.from.iter().chain(rule.to.iter()).cloned().collect();
+            union.sort();
+
+            assert_eq!(rule.antecedent_count(), count(rule.from()));
+            assert_eq!(rule.consequent_count(), count(rule.to()));
+            assert_eq!(rule.union_count(), count(&union));
+            assert!((rule.confidence() - rule.union_count() as f64 / rule.antecedent_count() as f64).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn surprise_rank_puts_a_rare_consequent_first() {
+        let mut baskets: Vec<&[&str]> = vec![&["a", "popular"], &["a", "popular"], &["b", "rare"], &["b", "rare"]];
+        baskets.extend([&["popular"][..]; 6]);
+        let result = AprioriConfig::new().min_support_count(2).min_confidence(0.9).run_on_txn_set(txns(&baskets)).unwrap();
+        let rules = &result.association_rules_set;
+
+        // a -> popular and b -> rare are both exact, but popular is in 8 of 10 baskets
+        let (to_popular, to_rare) = (rule(rules, &["a"], &["popular"]), rule(rules, &["b"], &["rare"]));
+        assert_eq!(to_popular.confidence(), to_rare.confidence());
+
+        let ranked = surprise_rank(rules, &result.fre_sets, 10);
+        let position = |rule| ranked.iter().position(|&x| x == rule).unwrap();
+        assert!(position(to_rare) < position(to_popular));
+        assert_eq!(position(to_popular), ranked.len() - 1);
+    }
+
+    #[test]
+    fn too_high_support_warns_that_there_are_no_rules() {
+        // whole milk and other vegetables, the most frequent pair, are in 7.5% of the baskets
+        let result = AprioriConfig::new().min_support(0.1).run_on_file(GROCERIES).unwrap();
+        assert!(result.fre_sets.iter().all(|x| x.degree == 1) && !result.fre_sets.is_empty());
+        assert!(result.association_rules_set.is_empty());
+        assert!(result.warnings.contains(&Warning::NoMultiItemPatterns { min_sup: 0.1 }));
+
+        let result = AprioriConfig::new().min_support(0.05).run_on_file(GROCERIES).unwrap();
+        assert!(!result.warnings.iter().any(|x| matches!(x, Warning::NoMultiItemPatterns { .. })));
+    }
+
+    #[test]
+    fn threshold_for_itemset_of_a_known_infrequent_itemset() {
+        let items = strings(&["I4", "I2", "I1"]);
+        let is_mined = |config: AprioriConfig| config.run_on_txn_set(textbook()).unwrap().fre_sets.iter().any(|x| x.items == strings(&["I1", "I2", "I4"]));
+
+        // in 1 of the 9 transactions, below the min_count of 2 of the textbook
+        let threshold = threshold_for_itemset(&items, &textbook());
+        assert_eq!(threshold, 1.0 / 9.0);
+        assert!(!is_mined(AprioriConfig::new().min_support_count(2)));
+        assert!(is_mined(AprioriConfig::new().min_support(threshold)));
+
+        assert_eq!(threshold_for_itemset(&strings(&["I4", "I5"]), &textbook()), 0.0);
+        assert_eq!(threshold_for_itemset(&items, &[]), 0.0);
+    }
+
+    #[test]
+    fn retained_transactions_match_the_input() {
+        let filename = temp_file("retained.csv", "milk,bread\nbread,eggs,bag\nmilk\n");
+        let config = AprioriConfig::new().has_headers(false).min_support(0.5).ignore_items(strings(&["bag"]));
+
+        let txn_set = config.clone().retain_transactions(true).run_on_file(&filename).unwrap().txn_set.unwrap();
+        let rows: Vec<(usize, Vec<String>)> = txn_set.into_iter().map(|x| (x.id, x.items)).collect();
+        assert_eq!(rows, vec![(0, strings(&["bread", "milk"])), (1, strings(&["bread", "eggs"])), (2, strings(&["milk"]))]);
+
+        assert!(config.run_on_file(&filename).unwrap().txn_set.is_none());
+    }
+
+    #[test]
+    fn lattice_hit_rates_of_the_textbook() {
+        let result = AprioriConfig::new().min_support_count(2).run_on_txn_set(textbook()).unwrap();
+
+        // all 5 items, 6 of the 10 pairs of frequent items, then {I1, I2, I3} and {I1, I2, I5} are the only CandicateSets left by pruning,
+        // and {I1, I2, I3, I5} is pruned since {I1, I3, I5} is not frequent
+        assert_eq!(lattice_hit_rates(&result.fre_sets, &result.candidate_counts), vec![(1, 1.0), (2, 0.6), (3, 1.0)]);
+    }
+
+    #[test]
+    fn collapsed_transactions_give_the_same_supports() {
+        let config = AprioriConfig::new().min_support(0.02).retain_transactions(true);
+        let plain = config.clone().run_on_file(GROCERIES).unwrap();
+        let collapsed = config.collapse_duplicate_transactions(true).run_on_file(GROCERIES).unwrap();
+
+        assert_eq!(counts(&collapsed.fre_sets), counts(&plain.fre_sets));
+        assert_eq!(collapsed.association_rules_set, plain.association_rules_set);
+
+        let (plain_txns, collapsed_txns) = (plain.txn_set.unwrap(), collapsed.txn_set.unwrap());
+        assert!(collapsed_txns.len() < plain_txns.len());
+        assert_eq!(txn_count(&collapsed_txns), plain_txns.len());
+    }
+
+    #[test]
+    fn core_items_of_the_textbook_by_degree() {
+        let core = core_items_by_degree(&textbook_fre_sets());
+        let owned = |items: &[(&str, usize)]| items.iter().map(|&(item, n)| (item.to_string(), n)).collect::<Vec<_>>();
+
+        assert_eq!(core.keys().copied().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(core[&2], owned(&[("I2", 4), ("I1", 3), ("I3", 2), ("I5", 2), ("I4", 1)]));
+        assert_eq!(core[&3], owned(&[("I1", 2), ("I2", 2), ("I3", 1), ("I5", 1)]));
+    }
+
+    #[test]
+    fn rules_drained_from_the_channel_on_another_thread_match_the_batch() {
+        let batch = AprioriConfig::new().min_support(0.02).min_confidence(0.2).run_on_file(GROCERIES).unwrap();
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(4);
+        let consumer = std::thread::spawn(move || receiver.iter().collect::<Vec<AssociationRule>>());
+        generate_rules_to_channel(&batch.fre_sets, 0.2, 9835, sender);
+        let mut drained = consumer.join().unwrap();
+
+        sort_rules_by_confidence(&mut drained);
+        assert_eq!(drained, batch.association_rules_set);
+    }
+
+    #[test]
+    fn targets_and_predictors_direct_the_rules() {
+        let config = AprioriConfig::new().min_support_count(2).min_confidence(0.2);
+        let full = config.clone().run_on_txn_set(textbook()).unwrap().association_rules_set;
+        let directed = config.clone().targets(strings(&["I3"])).predictors(strings(&["I1", "I2"]))
+            .run_on_txn_set(textbook()).unwrap().association_rules_set;
+
+        let splits: Vec<(Vec<String>, Vec<String>)> = directed.iter().map(|x| (x.from.clone(), x.to.clone())).collect();
+        assert_eq!(splits, vec![
+            (strings(&["I1"]), strings(&["I3"])),
+            (strings(&["I2"]), strings(&["I3"])),
+            (strings(&["I1", "I2"]), strings(&["I3"])),
+        ]);
+        assert!(directed.iter().all(|x| full.contains(x)));
+
+        // without predictors any other item can lead to the target
+        let targeted = config.targets(strings(&["I5"])).run_on_txn_set(textbook()).unwrap().association_rules_set;
+        let expected: Vec<AssociationRule> = full.iter().filter(|x| x.to == strings(&["I5"])).cloned().collect();
+        assert!(!expected.is_empty());
+        assert_eq!(targeted, expected);
+    }
+
+    #[test]
+    fn cluster_itemsets_links_similar_itemsets() {
+        let fre_sets = vec![
+            fre_set(&["a", "b", "c"], 2),
+            fre_set(&["a", "b", "d"], 2),
+            fre_set(&["a", "b", "d", "e"], 2),
+            fre_set(&["x", "y"], 2),
+            fre_set(&["x", "y", "z"], 2),
+            fre_set(&["q"], 2),
+        ];
+        assert_eq!(itemset_jaccard(&fre_sets[0].items, &fre_sets[1].items), 0.5);
+        assert_eq!(itemset_jaccard(&fre_sets[0].items, &fre_sets[2].items), 0.4);
+        assert_eq!(itemset_jaccard(&[], &[]), 0.0);
+
+        // {a, b, c} and {a, b, d, e} are only linked through {a, b, d}
+        assert_eq!(cluster_itemsets(&fre_sets, 0.5), vec![vec![0, 1, 2], vec![3, 4], vec![5]]);
+        assert_eq!(cluster_itemsets(&fre_sets, 0.7), vec![vec![0], vec![1, 2], vec![3], vec![4], vec![5]]);
+    }
+
+    #[test]
+    fn effective_min_sup_is_min_count_over_txn_num() {
+        // 0.03 * 9835 = 295.05 is truncated to a min_count of 295
+        let result = AprioriConfig::new().min_support(0.03).run_on_file(GROCERIES).unwrap();
+        assert_eq!(result.effective_min_sup, 295.0 / 9835.0);
+        assert!(result.fre_sets.iter().all(|x| x.support(9835) >= result.effective_min_sup));
+
+        let transactions = || vec![strings(&["a"]), strings(&["a", "b"]), strings(&["b"])];
+        let result = AprioriConfig::new().min_support_count(2).run_on_transactions(transactions()).unwrap();
+        assert_eq!(result.effective_min_sup, 2.0 / 3.0);
+
+        // under time decay the threshold is on the weights, not truncated
+        let result = AprioriConfig::new().min_support(0.4).time_decay(7.0).run_on_transactions(transactions()).unwrap();
+        assert!((result.effective_min_sup - 0.4).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lift_stability_is_deterministic_for_a_seed() {
+        let result = AprioriConfig::new().retain_transactions(true).run_on_file(GROCERIES).unwrap();
+        let txn_set = result.txn_set.unwrap();
+        let yogurt_milk = rule(&result.association_rules_set, &["yogurt"], &["whole milk"]);
+
+        let (mean, deviation) = lift_stability(yogurt_milk, &txn_set, 20, 0.5, 42);
+        assert_eq!(lift_stability(yogurt_milk, &txn_set, 20, 0.5, 42), (mean, deviation));
+        assert_ne!(lift_stability(yogurt_milk, &txn_set, 20, 0.5, 43), (mean, deviation));
+        assert!((mean - yogurt_milk.lift()).abs() < 0.1 && deviation > 0.0 && deviation < 0.1);
+
+        // every sample is the whole txn_set
+        let (mean, deviation) = lift_stability(yogurt_milk, &txn_set, 3, 1.0, 42);
+        assert!((mean - yogurt_milk.lift()).abs() < 1e-9 && deviation < 1e-9);
+        assert!(lift_stability(yogurt_milk, &txn_set, 0, 0.5, 42).0.is_nan());
+    }
+
+    #[test]
+    fn tier_rules_assigns_known_bands() {
+        let rules = textbook_rules();
+        let tiers = tier_rules(rules.clone(), &[0.6, 1.0]);
+        let tier_of = |from: &[&str], to: &[&str]| *tiers.iter().find(|x| x.1.contains(rule(&rules, from, to))).unwrap().0;
+
+        // conf 1, 4 / 6 and 4 / 7
+        assert_eq!(tier_of(&["I5"], &["I1"]), 0);
+        assert_eq!(tier_of(&["I1"], &["I3"]), 1);
+        assert_eq!(tier_of(&["I2"], &["I1"]), 2);
+
+        assert_eq!(tiers.values().map(|x| x.len()).sum::<usize>(), rules.len());
+        assert!(tiers[&0].iter().all(|x| x.conf == 1.0) && tiers[&2].iter().all(|x| x.conf < 0.6));
+        assert_eq!(tier_rules(rules.clone(), &[0.1]).keys().copied().collect::<Vec<_>>(), vec![0]);
+    }
+
+    #[test]
+    fn bloom_prefilter_gives_the_same_counts() {
+        let config = AprioriConfig::new().min_support(0.02).min_confidence(0.2);
+        let plain = config.clone().run_on_file(GROCERIES).unwrap();
+        let filtered = config.bloom_prefilter(true).run_on_file(GROCERIES).unwrap();
+
+        assert_eq!(counts(&filtered.fre_sets), counts(&plain.fre_sets));
+        assert_eq!(filtered.association_rules_set, plain.association_rules_set);
+    }
+
+    #[test]
+    fn confidence_asymmetry_of_a_known_asymmetric_pair() {
+        let asymmetries = confidence_asymmetry(&textbook_fre_sets(), 9);
+
+        // I4 -> I2 is exact, I2 -> I4 only at 2 / 7, the same for I5 and I2, {I1, I3} is symmetric with both rules at 4 / 6
+        let expected = [("I4", "I2", 5.0 / 7.0), ("I5", "I2", 5.0 / 7.0), ("I5", "I1", 2.0 / 3.0)];
+        for (asymmetry, (a, b, value)) in asymmetries.iter().zip(expected) {
+            assert_eq!((asymmetry.0.as_str(), asymmetry.1.as_str()), (a, b));
+            assert!((asymmetry.2 - value).abs() < 1e-12);
+        }
+        assert_eq!(asymmetries.len(), 6);
+        assert_eq!(asymmetries.last().unwrap().2, 0.0);
+    }
+
+    #[test]
+    fn marginal_item_effect_on_the_textbook_confidences() {
+        let fre_sets = textbook_fre_sets();
+
+        // conf(I1 -> I2) is 4 / 6, adding I5 makes it exact, adding I3 lowers it to 2 / 4, {I1, I4} is not frequent
+        let effects = marginal_item_effect(&strings(&["I1"]), &strings(&["I2"]), &fre_sets, 9);
+        assert_eq!(effects, vec![("I5".to_string(), 1.0), ("I3".to_string(), 0.5)]);
+
+        let effects = marginal_item_effect(&strings(&["I5"]), &strings(&["I1"]), &fre_sets, 9);
+        assert_eq!(effects, vec![("I2".to_string(), 1.0)]);
+    }
+
+    #[test]
+    fn apriori_returns_errors_on_bad_input() {
+        assert!(matches!(apriori(0.05, 0.3, "no_such_file.csv"), Err(AprioriError::Io(_))));
+        assert!(matches!(apriori(0.00001, 0.3, GROCERIES), Err(AprioriError::ZeroMinCount { txn_count: 9835, .. })));
+        assert!(matches!(apriori_from_transactions(0.5, 0.3, vec![]), Ok((fre_sets, rules)) if fre_sets.is_empty() && rules.is_empty()));
+
+        // the count given is named, not a min_sup it was never given
+        let zero_count = AprioriConfig::new().min_support_count(0).run_on_txn_set(textbook()).unwrap_err();
+        assert!(matches!(zero_count, AprioriError::ZeroMinSupportCount));
+        assert_eq!(zero_count.to_string(), "min_support_count is 0, every itemset would be frequent");
+        assert!(AprioriConfig::new().min_support_count(0).run_on_txn_set(vec![]).is_ok());
+    }
+
+    #[test]
+    fn conviction_is_infinite_for_a_perfectly_confident_rule() {
+        let rules = textbook_rules();
+
+        // I5 -> I1 is never wrong, I1 -> I3 at 4 / 6 with sup(I3) = 6 / 9
+        let exact = rule(&rules, &["I5"], &["I1"]);
+        assert_eq!(exact.confidence(), 1.0);
+        assert_eq!(exact.conviction(), f64::INFINITY);
+        let approximate = rule(&rules, &["I1"], &["I3"]);
+        assert!((approximate.conviction() - (1.0 - 6.0 / 9.0) / (1.0 - 4.0 / 6.0)).abs() < 1e-12);
+
+        assert!(rules.iter().all(|x| !x.conviction().is_nan()));
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn threads_count_and_generate_the_same_as_a_single_thread() {
+        let config = AprioriConfig::new().min_support(0.02).min_confidence(0.1);
+        let txn_set = create_sorted_txn_set(GROCERIES, &config).unwrap();
+        let weights = txn_weights(&txn_set, None);
+        let min_sup = MinSupport::new(0.02, txn_set.len(), &weights, false);
+        let result = config.run_on_file(GROCERIES).unwrap();
+
+        let candidates = || get_candi_from_f(&result.fre_sets, 2);
+        let mut single: Vec<CandicateSet> = candidates();
+        for candi_set in single.iter_mut() {
+            count_candidate(candi_set, &txn_set, &weights, None, &min_sup, false);
+        }
+        let candi_counts = |candi_sets: Vec<CandicateSet>| candi_sets.into_iter().map(|x| (x.items, x.count)).collect::<Vec<_>>();
+        assert_eq!(candi_counts(count_batch(candidates(), &txn_set, &weights, None, &min_sup, false)), candi_counts(single));
+
+        let mut single_rules = Vec::new();
+        for_each_rule(&result.fre_sets, &result.fre_sets, &config, 9835.0, &mut |rule| {
+            single_rules.push(rule);
+            ControlFlow::Continue(())
+        });
+        sort_rules_by_confidence(&mut single_rules);
+        assert_eq!(result.association_rules_set, single_rules);
+    }
+
+    #[test]
+    fn a_count_equal_to_min_count_is_frequent_at_every_degree() {
+        // min_count is 4 * 0.5 = 2
+        let txn_set = || txns(&[&["a", "b"], &["a", "b"], &["c"], &["c"]]);
+        let result = AprioriConfig::new().min_support(0.5).run_on_txn_set(txn_set()).unwrap();
+
+        let expected = vec![(strings(&["a"]), 2), (strings(&["b"]), 2), (strings(&["c"]), 2), (strings(&["a", "b"]), 2)];
+        assert_eq!(counts(&result.fre_sets), expected);
+
+        let result = AprioriConfig::new().min_support_count(3).run_on_txn_set(txn_set()).unwrap();
+        assert!(result.fre_sets.is_empty());
+    }
+
+    #[test]
+    fn a_headerless_file_of_5_lines_has_5_transactions() {
+        let filename = temp_file("headerless.csv", "milk,bread\nmilk\nbread,eggs,milk\neggs\nmilk,eggs\n");
+
+        let txn_set = create_sorted_txn_set(&filename, &AprioriConfig::new().has_headers(false)).unwrap();
+        assert_eq!(txn_set.len(), 5);
+        assert_eq!(txn_set[0].items, strings(&["bread", "milk"]));
+
+        // as in groceries.csv, the first line is a header by default
+        let txn_set = create_sorted_txn_set(&filename, &AprioriConfig::new()).unwrap();
+        assert_eq!(txn_set.len(), 4);
+        assert_eq!(txn_set[0].items, strings(&["milk"]));
+    }
+
+    #[test]
+    fn a_single_frequent_item_gives_no_candidates_and_no_panic() {
+        let result = AprioriConfig::new().min_support(0.5).run_on_txn_set(txns(&[&["a", "b"], &["a", "c"], &["a", "d"], &["a", "e"]])).unwrap();
+
+        assert_eq!(counts(&result.fre_sets), vec![(strings(&["a"]), 4)]);
+        assert!(result.association_rules_set.is_empty());
+        assert!(minimal_generators(&result.fre_sets).len() == 1 && non_redundant_rules(&result.fre_sets, 0.5, 4).is_empty());
+    }
+
+    #[test]
+    fn every_split_of_a_degree_20_set_is_tried_without_overflow() {
+        let items: Vec<String> = (0..20).map(|x| format!("item{:02}", x)).collect();
+        let names: Vec<&str> = items.iter().map(|x| x.as_str()).collect();
+        let big = fre_set(&names, 2);
+        let mut fre_sets: Vec<FrequentSet> = (0..20).map(|x| fre_set(&[names[x]], 2)).collect();
+        fre_sets.extend((0..20).map(|skip| fre_set(&without_item(&names, skip), 2)));
+
+        // all 2^20 - 2 splits, only those with one item on a side have both sides in fre_sets
+        let config = AprioriConfig::new().min_confidence(0.5).rule_degree_soft_limit(None);
+        let mut rules = Vec::new();
+        for_each_rule([&big], &fre_sets, &config, 4.0, &mut |rule| {
+            rules.push(rule);
+            ControlFlow::Continue(())
+        });
+
+        assert_eq!(rules.len(), 40);
+        assert_eq!(rules.iter().filter(|x| x.to.len() == 1).count(), 20);
+    }
+
+    #[test]
+    fn thresholds_out_of_range_are_rejected() {
+        let run = |config: AprioriConfig| config.run_on_txn_set(textbook());
+
+        for value in [0.0, -0.1, 1.5, f64::NAN] {
+            assert!(matches!(run(AprioriConfig::new().min_support(value)), Err(AprioriError::InvalidThreshold { name: "min_sup", .. })));
+            assert!(matches!(run(AprioriConfig::new().min_confidence(value)), Err(AprioriError::InvalidThreshold { name: "min_conf", .. })));
+        }
+        assert!(run(AprioriConfig::new().min_support(1.0).min_confidence(1.0)).is_ok());
+        assert!(matches!(apriori(1.5, 0.3, GROCERIES), Err(AprioriError::InvalidThreshold { name: "min_sup", value: 1.5 })));
+    }
+
+    #[test]
+    fn eclat_finds_the_fre_sets_of_apriori() {
+        let txn_set = create_sorted_txn_set(GROCERIES, &AprioriConfig::new()).unwrap();
+        let (fre_sets, _) = apriori(0.02, 0.3, GROCERIES).unwrap();
+        assert_eq!(counts(&eclat(0.02, &txn_set).unwrap()), counts(&fre_sets));
+
+        let textbook_eclat = eclat(2.0 / 9.0, &textbook()).unwrap();
+        assert_eq!(counts(&textbook_eclat), counts(&textbook_fre_sets()));
+        assert!(matches!(eclat(0.0, &txn_set), Err(AprioriError::InvalidThreshold { .. })));
+    }
+
+    #[test]
+    fn csv_fields_are_trimmed() {
+        let txn_set: Vec<Txn> = csv_txns("a, b ,c\n b,, \n".as_bytes(), &AprioriConfig::new().has_headers(false))
+            .collect::<Result<_, _>>().unwrap();
+
+        assert_eq!(txn_set[0].items, strings(&["a", "b", "c"]));
+        assert_eq!(txn_set[1].items, strings(&["b"]));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_transactions_are_read_with_their_escapes() {
+        let filename = temp_file("txns.json", r#" [ ["milk", " bread "], [], ["caf\u00e9", "tab\there", "\"quoted\"", "\ud83e\udd50", ""] ]
+"#);
+        let txn_set = create_txn_set_from_json(&filename).unwrap();
+
+        let items: Vec<Vec<String>> = txn_set.iter().map(|x| x.items.clone()).collect();
+        assert_eq!(items, vec![
+            strings(&["bread", "milk"]),
+            vec![],
+            strings(&["\"quoted\"", "café", "tab\there", "🥐"]),
+        ]);
+        assert_eq!(txn_set.iter().map(|x| x.id).collect::<Vec<_>>(), vec![0, 1, 2]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_which_is_not_an_array_of_arrays_of_strings_is_rejected() {
+        let json_error = |content: &str| match create_txn_set_from_json(&temp_file("bad.json", content)) {
+            Err(AprioriError::Json { offset, .. }) => offset,
+            other => panic!("{:?} for {}", other.map(|x| x.len()), content),
+        };
+
+        for content in [r#"[["a"], [1]]"#, r#"{"a": ["b"]}"#, r#"["a"]"#, r#"[["a"]"#, r#"[["a\q"]]"#, r#"[["\ud83e"]]"#, ""] {
+            json_error(content);
+        }
+        // the whole array is read before the trailing content is found
+        assert_eq!(json_error(r#"[["a"]] ["b"]"#), 8);
+    }
+
+    #[test]
+    fn nullable_rows_drop_their_absent_items() {
+        let some = |x: &str| Some(x.to_string());
+        let txn_set = txns_from_nullable_rows(vec![
+            vec![some("milk"), None, some("bread")],
+            vec![None, None, some("")],
+            vec![some("milk"), None, None],
+        ]);
+
+        let rows: Vec<(usize, Vec<String>)> = txn_set.iter().map(|x| (x.id, x.items.clone())).collect();
+        assert_eq!(rows, vec![(0, strings(&["bread", "milk"])), (1, vec![]), (2, strings(&["milk"]))]);
+
+        // the empty row still counts towards support
+        let result = AprioriConfig::new().min_support_count(2).run_on_txn_set(txn_set).unwrap();
+        assert_eq!(counts(&result.fre_sets), vec![(strings(&["milk"]), 2)]);
+        assert_eq!(result.effective_min_sup, 2.0 / 3.0);
+    }
+
+    #[test]
+    fn pairs_are_grouped_into_transactions() {
+        let pair = |id: u32, item: Option<&str>| (id, item.map(|x| x.to_string()));
+        let txn_set = txns_from_pairs(vec![
+            pair(7, Some("milk")),
+            pair(3, Some("bread")),
+            pair(7, Some("bread")),
+            pair(9, None),
+            pair(3, None),
+            pair(7, Some("milk")),
+            pair(9, Some("")),
+        ]);
+
+        // in the order the ids first appear, 9 has only NULL items and is kept empty
+        let rows: Vec<(usize, Vec<String>)> = txn_set.iter().map(|x| (x.id, x.items.clone())).collect();
+        assert_eq!(rows, vec![(0, strings(&["bread", "milk"])), (1, strings(&["bread"])), (2, vec![])]);
+        assert!(txns_from_pairs(Vec::<(u32, Option<String>)>::new()).is_empty());
+    }
+
+    #[test]
+    fn benchmark_file_counts_the_rules_without_collecting_them() {
+        let finished = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let reported = finished.clone();
+        let config = AprioriConfig::new().min_support(0.01).min_confidence(0.1);
+        let expected = config.clone().run_on_file(GROCERIES).unwrap().association_rules_set.len();
+        assert!(expected > 0);
+
+        let timings = config.clone().progress(move |event| if let ProgressEvent::RulesFinished { count, .. } = event {
+            reported.lock().unwrap().push(count);
+        }).benchmark_file(GROCERIES).unwrap();
+        assert!(timings.rules > Duration::ZERO);
+        assert_eq!(*finished.lock().unwrap(), vec![expected]);
+
+        let mut counting = config.clone();
+        counting.count_rules_only = true;
+        let result = counting.run_on_file(GROCERIES).unwrap();
+        assert!(result.association_rules_set.is_empty());
+        assert_eq!(result.memory.rules_bytes, 0);
+
+        counting.non_redundant = true;
+        assert!(counting.run_on_file(GROCERIES).unwrap().association_rules_set.is_empty());
+    }
+
+
+    #[test]
+    fn generation_to_the_channel_stops_once_the_receiver_is_gone() {
+        // the full lattice of 18 items, all 3^18 splits of it would take minutes
+        let items: Vec<String> = (0..18).map(|x| format!("i{:02}", x)).collect();
+        let fre_sets: Vec<FrequentSet> = (1..1u32 << 18).map(|bits| {
+            let subset: Vec<&str> = items.iter().enumerate().filter(|(pos, _)| bits & (1 << pos) != 0).map(|(_, x)| x.as_str()).collect();
+            fre_set(&subset, 5)
+        }).collect();
+
+        let (sender, receiver) = std::sync::mpsc::sync_channel(1);
+        let (done, finished) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            generate_rules_to_channel(&fre_sets, 0.5, 5, sender);
+            done.send(()).unwrap();
+        });
+
+        assert!(receiver.recv().is_ok());
+        drop(receiver);
+        assert!(finished.recv_timeout(Duration::from_secs(30)).is_ok());
+    }
+
+    #[test]
+    fn for_each_rule_emits_nothing_after_a_break() {
+        let fre_sets = textbook_fre_sets();
+        let config = AprioriConfig::new().min_confidence(0.5);
+        let mut emitted = 0;
+        for_each_rule(&fre_sets, &fre_sets, &config, 9.0, &mut |_| {
+            emitted += 1;
+            if emitted == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+
+        assert_eq!(emitted, 3);
+        assert!(textbook_rules().len() > 3);
+    }
+
+
+    #[test]
+    fn max_rules_keeps_the_first_of_the_sorted_rules_ties_included() {
+        let unbounded = textbook_rules();
+        let config = AprioriConfig::new().min_support_count(2).min_confidence(0.5);
+        assert_eq!(config.max_rules, None);
+
+        // some n cut through rules of the same conf and sup, only from and to order them
+        let tied = (1..unbounded.len()).filter(|&n| unbounded[n - 1].conf == unbounded[n].conf && unbounded[n - 1].sup == unbounded[n].sup).count();
+        assert!(tied > 0);
+
+        for n in 0..unbounded.len() + 3 {
+            let bounded = config.clone().max_rules(n).run_on_txn_set(textbook()).unwrap().association_rules_set;
+            assert_eq!(bounded, unbounded[..n.min(unbounded.len())]);
+        }
+    }
+
+
+    #[test]
+    fn top_rules_are_the_first_of_the_sorted_rules() {
+        let full = AprioriConfig::new().min_support(0.01).min_confidence(0.2).run_on_file(GROCERIES).unwrap();
+        assert!(full.association_rules_set.len() > 50);
+
+        for n in [1, 10, 50, full.association_rules_set.len() + 1] {
+            let top = top_rules(&full.fre_sets, 0.2, 9835, n);
+            assert_eq!(top, full.association_rules_set[..n.min(full.association_rules_set.len())]);
+        }
+    }
+
+
+    #[test]
+    fn tracked_tids_are_the_ids_of_the_txns_holding_the_set() {
+        // ids out of order, as hand-built Txns may have them
+        let shuffled = || textbook().into_iter().map(|x| Txn::new(100 - x.id * 7 % 9, x.items)).collect::<Vec<Txn>>();
+        let txn_set = shuffled();
+        let config = AprioriConfig::new().min_support_count(2);
+
+        let tracked = config.clone().track_tids(true).run_on_txn_set(shuffled()).unwrap().fre_sets;
+        assert!(tracked.iter().any(|x| x.degree == 3));
+        for fre_set in &tracked {
+            let mut expected: Vec<usize> = txn_set.iter().filter(|x| fre_set.items.iter().all(|item| x.items.contains(item))).map(|x| x.id).collect();
+            expected.sort_unstable();
+            assert_eq!(fre_set.tids(), expected, "{:?}", fre_set.items);
+            assert_eq!(fre_set.tids().len(), fre_set.count);
+        }
+
+        let untracked = config.clone().run_on_txn_set(shuffled()).unwrap().fre_sets;
+        assert!(untracked.iter().all(|x| x.tids().is_empty()));
+
+        // {I1, I2, I5} without {I1, I5} and {I2, I5} to join it from is scanned
+        let mut partial = vec![fre_set(&["I1"], 6), fre_set(&["I2"], 7), fre_set(&["I1", "I2", "I5"], 2)];
+        fill_tids(&mut partial, &txn_set);
+        let mut expected = vec![100, 100 - 7 * 7 % 9];
+        expected.sort_unstable();
+        assert_eq!(partial[2].tids(), expected);
+    }
+
+
+    /// the items and counts of the hand-checked FrequentSets, in any order
+    fn expected_counts(sets: &[(&[&str], usize)]) -> Vec<(Vec<String>, usize)> {
+        let mut expected: Vec<(Vec<String>, usize)> = sets.iter().map(|(items, count)| (strings(items), *count)).collect();
+        expected.sort();
+        expected
+    }
+
+    #[test]
+    fn maximal_frequent_sets_of_the_textbook_lattice() {
+        // {I2, I4} and the two 3-FrequentSets have no frequent superset, every other set is inside one of them
+        let mut maximal = counts(&maximal_frequent_sets(&textbook_fre_sets()));
+        maximal.sort();
+        assert_eq!(maximal, expected_counts(&[(&["I2", "I4"], 2), (&["I1", "I2", "I3"], 2), (&["I1", "I2", "I5"], 2)]));
+
+        // a set with a frequent superset is dropped whatever their counts
+        let lattice = [fre_set(&["a"], 5), fre_set(&["b"], 3), fre_set(&["c"], 3), fre_set(&["a", "b"], 3)];
+        assert_eq!(counts(&maximal_frequent_sets(&lattice)), vec![(strings(&["c"]), 3), (strings(&["a", "b"]), 3)]);
+    }
+
+
+    #[test]
+    fn closed_frequent_sets_of_the_textbook_lattice() {
+        // {I4} and {I5} have the count of {I2, I4} and {I1, I5}, {I1, I5} and {I2, I5} the one of {I1, I2, I5}
+        let mut closed = counts(&closed_frequent_sets(&textbook_fre_sets()));
+        closed.sort();
+        assert_eq!(closed, expected_counts(&[
+            (&["I1"], 6), (&["I2"], 7), (&["I3"], 6),
+            (&["I1", "I2"], 4), (&["I1", "I3"], 4), (&["I2", "I3"], 4), (&["I2", "I4"], 2),
+            (&["I1", "I2", "I3"], 2), (&["I1", "I2", "I5"], 2),
+        ]));
+
+        // {a} shares its count with its superset {a, b}, {c} only with {a, b}, which does not hold it
+        let lattice = [fre_set(&["a"], 3), fre_set(&["b"], 4), fre_set(&["c"], 3), fre_set(&["a", "b"], 3)];
+        assert_eq!(counts(&closed_frequent_sets(&lattice)), vec![(strings(&["b"]), 4), (strings(&["c"]), 3), (strings(&["a", "b"]), 3)]);
+    }
+
+
+    /// ## a json writer for the round-trip tests, only what the structs of the crate need
+    ///
+    /// numbers, strings, options, sequences and structs, anything else is an error
+    #[cfg(feature = "serde")]
+    struct JsonWriter(String);
+
+    #[cfg(feature = "serde")]
+    impl serde::ser::Error for JsonError {
+        fn custom<T: std::fmt::Display>(msg: T) -> Self {
+            JsonError(msg.to_string())
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl JsonWriter {
+        fn unsupported<T>(&self, what: &str) -> Result<T, JsonError> {
+            Err(JsonError(format!("{} can't be written", what)))
+        }
+
+        /// a comma before every element but the first, first is true for an empty array or object
+        fn separate(&mut self) {
+            if !self.0.ends_with(['[', '{']) {
+                self.0.push(',');
+            }
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl serde::Serializer for &mut JsonWriter {
+        type Ok = ();
+        type Error = JsonError;
+        type SerializeSeq = Self;
+        type SerializeTuple = serde::ser::Impossible<(), JsonError>;
+        type SerializeTupleStruct = serde::ser::Impossible<(), JsonError>;
+        type SerializeTupleVariant = serde::ser::Impossible<(), JsonError>;
+        type SerializeMap = serde::ser::Impossible<(), JsonError>;
+        type SerializeStruct = Self;
+        type SerializeStructVariant = serde::ser::Impossible<(), JsonError>;
+
+        fn serialize_bool(self, v: bool) -> Result<(), JsonError> {
+            self.0.push_str(if v { "true" } else { "false" });
+            Ok(())
+        }
+        fn serialize_i8(self, v: i8) -> Result<(), JsonError> { self.serialize_i64(v as i64) }
+        fn serialize_i16(self, v: i16) -> Result<(), JsonError> { self.serialize_i64(v as i64) }
+        fn serialize_i32(self, v: i32) -> Result<(), JsonError> { self.serialize_i64(v as i64) }
+        fn serialize_i64(self, v: i64) -> Result<(), JsonError> {
+            self.0.push_str(&v.to_string());
+            Ok(())
+        }
+        fn serialize_u8(self, v: u8) -> Result<(), JsonError> { self.serialize_u64(v as u64) }
+        fn serialize_u16(self, v: u16) -> Result<(), JsonError> { self.serialize_u64(v as u64) }
+        fn serialize_u32(self, v: u32) -> Result<(), JsonError> { self.serialize_u64(v as u64) }
+        fn serialize_u64(self, v: u64) -> Result<(), JsonError> {
+            self.0.push_str(&v.to_string());
+            Ok(())
+        }
+        fn serialize_f32(self, v: f32) -> Result<(), JsonError> { self.serialize_f64(v as f64) }
+        fn serialize_f64(self, v: f64) -> Result<(), JsonError> {
+            if !v.is_finite() {
+                return self.unsupported("a non-finite number");
+            }
+            // Debug is the shortest text parsing back to v, with a fraction so it is read as a float
+            self.0.push_str(&format!("{:?}", v));
+            Ok(())
+        }
+        fn serialize_char(self, v: char) -> Result<(), JsonError> { self.serialize_str(&v.to_string()) }
+        fn serialize_str(self, v: &str) -> Result<(), JsonError> {
+            self.0.push('"');
+            for c in v.chars() {
+                match c {
+                    '"' | '\\' => {
+                        self.0.push('\\');
+                        self.0.push(c);
+                    },
+                    c if c < ' ' => self.0.push_str(&format!("\\u{:04x}", c as u32)),
+                    c => self.0.push(c),
+                }
+            }
+            self.0.push('"');
+            Ok(())
+        }
+        fn serialize_bytes(self, _: &[u8]) -> Result<(), JsonError> { self.unsupported("bytes") }
+        fn serialize_none(self) -> Result<(), JsonError> { self.serialize_unit() }
+        fn serialize_some<T: serde::Serialize + ?Sized>(self, value: &T) -> Result<(), JsonError> { value.serialize(self) }
+        fn serialize_unit(self) -> Result<(), JsonError> {
+            self.0.push_str("null");
+            Ok(())
+        }
+        fn serialize_unit_struct(self, _: &'static str) -> Result<(), JsonError> { self.serialize_unit() }
+        fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<(), JsonError> { self.unsupported("an enum") }
+        fn serialize_newtype_struct<T: serde::Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<(), JsonError> { value.serialize(self) }
+        fn serialize_newtype_variant<T: serde::Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<(), JsonError> { self.unsupported("an enum") }
+        fn serialize_seq(self, _: Option<usize>) -> Result<Self, JsonError> {
+            self.0.push('[');
+            Ok(self)
+        }
+        fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, JsonError> { self.unsupported("a tuple") }
+        fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct, JsonError> { self.unsupported("a tuple") }
+        fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeTupleVariant, JsonError> { self.unsupported("an enum") }
+        fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, JsonError> { self.unsupported("a map") }
+        fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, JsonError> {
+            self.0.push('{');
+            Ok(self)
+        }
+        fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeStructVariant, JsonError> { self.unsupported("an enum") }
+    }
+
+    #[cfg(feature = "serde")]
+    impl serde::ser::SerializeSeq for &mut JsonWriter {
+        type Ok = ();
+        type Error = JsonError;
+
+        fn serialize_element<T: serde::Serialize + ?Sized>(&mut self, value: &T) -> Result<(), JsonError> {
+            self.separate();
+            value.serialize(&mut **self)
+        }
+        fn end(self) -> Result<(), JsonError> {
+            self.0.push(']');
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    impl serde::ser::SerializeStruct for &mut JsonWriter {
+        type Ok = ();
+        type Error = JsonError;
+
+        fn serialize_field<T: serde::Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), JsonError> {
+            self.separate();
+            serde::Serializer::serialize_str(&mut **self, key)?;
+            self.0.push(':');
+            value.serialize(&mut **self)
+        }
+        fn end(self) -> Result<(), JsonError> {
+            self.0.push('}');
+            Ok(())
+        }
+    }
+
+    /// value written by JsonWriter and read back by JsonReader
+    #[cfg(feature = "serde")]
+    fn json_round_trip<T: serde::Serialize + for<'de> serde::Deserialize<'de>>(value: &T) -> (String, T) {
+        let mut writer = JsonWriter(String::new());
+        value.serialize(&mut writer).unwrap();
+
+        let mut reader = JsonReader { buf: writer.0.as_bytes(), pos: 0 };
+        let read = T::deserialize(&mut reader).unwrap();
+        reader.end().unwrap();
+
+        (writer.0, read)
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn rules_and_frequent_sets_survive_a_json_round_trip() {
+        let rules = textbook_rules();
+        // exact rules have an infinite conviction, which is written as null
+        assert!(rules.iter().any(|x| x.conviction.is_infinite()));
+
+        let (json, read) = json_round_trip(&rules);
+        assert!(json.starts_with(r#"[{"from":["I1","I5"],"to":["I2"],"sup":0.2222222222222222,"conf":1.0,"#));
+        assert!(json.contains(r#""union_count":2,"conviction":null}"#));
+        assert_eq!(read, rules);
+
+        // a rule written without its conviction gets it back from conf and lift
+        let mut reader = JsonReader { buf: br#"{"from": ["a"], "to": ["b"], "sup": 0.25, "conf": 0.5, "lift": 1.0, "leverage": 0.0,
+            "antecedent_count": 2, "consequent_count": 2, "union_count": 1}"#, pos: 0 };
+        let read: AssociationRule = serde::Deserialize::deserialize(&mut reader).unwrap();
+        assert_eq!(read.conviction, 1.0);
+
+        let mut fre_sets = AprioriConfig::new().min_support_count(2).track_tids(true).run_on_txn_set(textbook()).unwrap().fre_sets;
+        let (json, read) = json_round_trip(&fre_sets);
+        assert!(!json.contains("tids"));
+        assert!(read.iter().all(|x: &FrequentSet| x.tids().is_empty()));
+        fre_sets.iter_mut().for_each(|x| x.tids.clear());
+        assert_eq!(counts(&read), counts(&fre_sets));
+        assert_eq!(read.iter().map(|x| (x.degree, x.weighted_count)).collect::<Vec<_>>(), fre_sets.iter().map(|x| (x.degree, x.weighted_count)).collect::<Vec<_>>());
+
+        // unknown fields are skipped and the fields are read in any order
+        let mut reader = JsonReader { buf: br#"{"count": 3, "extra": [1, {"a": null}], "items": ["b", "a"], "weighted_count": 3.0, "degree": 2}"#, pos: 0 };
+        let read: FrequentSet = serde::Deserialize::deserialize(&mut reader).unwrap();
+        assert_eq!(counts(&[read]), vec![(strings(&["b", "a"]), 3)]);
+    }
+
+
+    #[test]
+    fn write_rules_writes_the_numbered_rules() {
+        let rules = textbook_rules();
+        let picked = [rule(&rules, &["I4"], &["I2"]).clone(), rule(&rules, &["I1", "I2"], &["I5"]).clone()];
+
+        let mut written: Vec<u8> = Vec::new();
+        write_rules(&mut written, &picked).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), concat!(
+            "\nrule 0:\n[\n    \"I4\",\n] --> [\n    \"I2\",\n], sup = 0.2222222222222222, conf = 1\n",
+            "\nrule 1:\n[\n    \"I1\",\n    \"I2\",\n] --> [\n    \"I5\",\n], sup = 0.2222222222222222, conf = 0.5\n",
+        ));
+
+        let mut empty: Vec<u8> = Vec::new();
+        write_rules(&mut empty, &[]).unwrap();
+        assert!(empty.is_empty());
+    }
+
+
+    #[test]
+    fn write_rules_to_csv_writes_a_header_and_quotes_the_commas() {
+        let rules = textbook_rules();
+        let mut quoted = rule(&rules, &["I4"], &["I2"]).clone();
+        quoted.from = strings(&["I4", "salt, fine"]);
+        let picked = [quoted, rule(&rules, &["I1", "I2"], &["I5"]).clone()];
+
+        let filename = temp_file("rules.csv", "");
+        write_rules_to_csv(&filename, &picked).unwrap();
+        assert_eq!(std::fs::read_to_string(&filename).unwrap(), concat!(
+            "antecedent,consequent,support,confidence,lift\n",
+            "\"I4|salt, fine\",I2,0.2222222222222222,1,1.2857142857142858\n",
+            "I1|I2,I5,0.2222222222222222,0.5,2.25\n",
+        ));
+    }
+
+
+    #[test]
+    fn rules_and_frequent_sets_display_as_braced_itemsets() {
+        let rules = textbook_rules();
+        assert_eq!(rule(&rules, &["I1", "I2"], &["I5"]).to_string(), "{I1, I2} => {I5} (sup=0.222, conf=0.500)");
+        assert_eq!(rule(&rules, &["I2"], &["I1"]).to_string(), "{I2} => {I1} (sup=0.444, conf=0.571)");
+
+        assert_eq!(fre_set(&["bread", "milk"], 42).to_string(), "{bread, milk} (count=42)");
+        assert_eq!(fre_set(&["I3"], 6).to_string(), "{I3} (count=6)");
+    }
+
+
+    /// the from and to of every rule, in order
+    fn splits<'a>(rules: impl IntoIterator<Item = &'a AssociationRule>) -> Vec<(Vec<String>, Vec<String>)> {
+        rules.into_iter().map(|x| (x.from.clone(), x.to.clone())).collect()
+    }
+
+    /// a from -> to split of strs
+    fn split(from: &[&str], to: &[&str]) -> (Vec<String>, Vec<String>) {
+        (strings(from), strings(to))
+    }
+
+    #[test]
+    fn filter_rules_by_lift_keeps_the_rules_from_min_lift_up_in_order() {
+        // I1 -> I3 and I3 -> I1 have a lift of exactly 1.0 and are kept
+        let filtered = filter_rules_by_lift(textbook_rules(), 1.0);
+        assert_eq!(splits(&filtered), vec![
+            split(&["I1", "I5"], &["I2"]), split(&["I2", "I5"], &["I1"]), split(&["I4"], &["I2"]), split(&["I5"], &["I1"]),
+            split(&["I5"], &["I1", "I2"]), split(&["I5"], &["I2"]), split(&["I1"], &["I3"]), split(&["I3"], &["I1"]),
+            split(&["I1", "I2"], &["I5"]),
+        ]);
+
+        assert_eq!(filter_rules_by_lift(textbook_rules(), 2.25).len(), 2);
+        assert!(filter_rules_by_lift(textbook_rules(), 2.5).is_empty());
+    }
+
+
+    #[test]
+    fn consequent_must_contain_keeps_the_rules_leading_to_the_item() {
+        let config = AprioriConfig::new().min_support_count(2).min_confidence(0.2);
+        let full = config.clone().run_on_txn_set(textbook()).unwrap().association_rules_set;
+        let to_i5 = config.clone().consequent_must_contain("I5".to_string()).run_on_txn_set(textbook()).unwrap().association_rules_set;
+
+        assert_eq!(splits(&to_i5), vec![
+            split(&["I1", "I2"], &["I5"]),
+            split(&["I1"], &["I2", "I5"]), split(&["I1"], &["I5"]),
+            split(&["I2"], &["I1", "I5"]), split(&["I2"], &["I5"]),
+        ]);
+        assert_eq!(to_i5, full.into_iter().filter(|x| x.to.contains(&"I5".to_string())).collect::<Vec<_>>());
+
+        // an item which is not frequent gives no rule
+        assert!(config.clone().consequent_must_contain("I9".to_string()).run_on_txn_set(textbook()).unwrap().association_rules_set.is_empty());
+    }
+
+
+    #[test]
+    fn single_item_consequents_keeps_the_rules_with_one_item_as_to() {
+        let config = AprioriConfig::new().min_support_count(2).min_confidence(0.2);
+        let full = config.clone().run_on_txn_set(textbook()).unwrap().association_rules_set;
+        let single = config.clone().single_item_consequents(true).run_on_txn_set(textbook()).unwrap().association_rules_set;
+
+        // I5 -> {I1, I2}, I1 -> {I2, I5} and the others with two items as to are gone, the from still has any size
+        assert!(full.iter().any(|x| x.to.len() == 2));
+        assert!(single.iter().any(|x| x.from.len() == 2));
+        assert_eq!(single, full.into_iter().filter(|x| x.to.len() == 1).collect::<Vec<_>>());
+        assert_eq!(splits(single.iter().filter(|x| x.from.len() == 2)), vec![
+            split(&["I1", "I5"], &["I2"]), split(&["I2", "I5"], &["I1"]),
+            split(&["I1", "I2"], &["I3"]), split(&["I1", "I2"], &["I5"]), split(&["I1", "I3"], &["I2"]), split(&["I2", "I3"], &["I1"]),
+        ]);
+    }
+
+
+    #[test]
+    fn predict_fires_the_rules_whose_from_is_in_the_basket() {
+        let rules = textbook_rules();
+
+        // in any order and with repeats, {I1, I5} fires the rules from {I1}, {I5} and {I1, I5}
+        let fired = predict(&rules, &strings(&["I5", "I1", "I5"]));
+        assert_eq!(splits(fired), vec![
+            split(&["I1", "I5"], &["I2"]), split(&["I5"], &["I1"]), split(&["I5"], &["I1", "I2"]), split(&["I5"], &["I2"]),
+            split(&["I1"], &["I2"]), split(&["I1"], &["I3"]),
+        ]);
+
+        assert_eq!(splits(predict(&rules, &strings(&["I4"]))), vec![split(&["I4"], &["I2"])]);
+        assert!(predict(&rules, &strings(&["I9"])).is_empty());
+        assert!(predict(&rules, &[]).is_empty());
+    }
+
+
+    #[test]
+    fn support_of_looks_up_the_frequent_itemsets() {
+        let fre_sets = textbook_fre_sets();
+
+        assert_eq!(support_of(&fre_sets, &strings(&["I2"]), 9), Some(7.0 / 9.0));
+        // in any order and with repeats
+        assert_eq!(support_of(&fre_sets, &strings(&["I2", "I1", "I2"]), 9), Some(4.0 / 9.0));
+        assert_eq!(support_of(&fre_sets, &strings(&["I5", "I2", "I1"]), 9), Some(2.0 / 9.0));
+
+        // {I1, I4} is in one txn only and {I9} in none, neither is frequent
+        assert_eq!(support_of(&fre_sets, &strings(&["I1", "I4"]), 9), None);
+        assert_eq!(support_of(&fre_sets, &strings(&["I9"]), 9), None);
+        assert_eq!(support_of(&fre_sets, &strings(&["I1"]), 0), None);
+    }
+
+
+    #[test]
+    fn leverage_is_the_support_above_independence() {
+        let rules = textbook_rules();
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+
+        // 2/9 - 2/9 * 4/9, 4/9 - 6/9 * 6/9 and 2/9 - 4/9 * 7/9
+        assert!(close(rule(&rules, &["I5"], &["I1", "I2"]).leverage(), 10.0 / 81.0));
+        assert!(close(rule(&rules, &["I1"], &["I3"]).leverage(), 0.0));
+        assert!(close(rule(&rules, &["I1", "I3"], &["I2"]).leverage(), -10.0 / 81.0));
+
+        for x in &rules {
+            assert!(close(x.leverage(), x.sup - x.antecedent_count as f64 / 9.0 * (x.consequent_count as f64 / 9.0)));
+            // leverage is above 0.0 exactly when lift is above 1.0, from and to occurring together more often than by chance
+            assert_eq!(x.leverage() > 1e-12, x.lift > 1.0 + 1e-12);
+        }
+    }
+
+
+    /// the items and counts mined at min_count 2 from the three baskets of the reader tests, sorted
+    fn basket_counts() -> Vec<(Vec<String>, usize)> {
+        expected_counts(&[(&["bread"], 3), (&["butter"], 2), (&["milk"], 2), (&["bread", "butter"], 2), (&["bread", "milk"], 2)])
+    }
+
+    /// the items and counts of fre_sets, sorted
+    fn sorted_counts(fre_sets: &[FrequentSet]) -> Vec<(Vec<String>, usize)> {
+        let mut sorted = counts(fre_sets);
+        sorted.sort();
+        sorted
+    }
+
+    #[test]
+    fn delimiter_splits_semicolon_and_tab_separated_files() {
+        let config = AprioriConfig::new().min_support_count(2);
+        for (name, delimiter) in [("baskets_semicolon.csv", b';'), ("baskets_tab.tsv", b'\t')] {
+            let separator = (delimiter as char).to_string();
+            let content = ["item1;item2;item3", "milk;bread", "milk;bread;butter", "bread;butter", ""].join("\n").replace(';', &separator);
+            let filename = temp_file(name, &content);
+
+            let result = config.clone().delimiter(delimiter).run_on_file(&filename).unwrap();
+            assert_eq!(sorted_counts(&result.fre_sets), basket_counts());
+
+            // with the default comma every line is one item
+            let whole_lines = config.clone().min_support_count(1).run_on_file(&filename).unwrap();
+            assert_eq!(whole_lines.fre_sets.iter().map(|x| x.degree).max(), Some(1));
+            assert!(whole_lines.fre_sets.iter().any(|x| x.items == strings(&[&format!("milk{}bread", separator)])));
+        }
+    }
+
+
+    #[test]
+    fn lines_files_skip_blank_lines_and_repeated_separators() {
+        let filename = temp_file("baskets.dat", "milk bread\n\n  milk  bread butter \n   \nbread butter\n");
+        let txn_set = create_txn_set_from_lines(&filename, ' ').unwrap();
+
+        assert_eq!(txn_set.iter().map(|x| (x.id, x.items.clone())).collect::<Vec<_>>(), vec![
+            (0, strings(&["bread", "milk"])), (1, strings(&["bread", "butter", "milk"])), (2, strings(&["bread", "butter"])),
+        ]);
+
+        let result = AprioriConfig::new().min_support_count(2).run_on_txn_set(txn_set).unwrap();
+        assert_eq!(sorted_counts(&result.fre_sets), basket_counts());
+        assert_eq!(result.effective_min_sup, 2.0 / 3.0);
+    }
+
+
+    #[test]
+    fn run_on_reader_mines_csv_bytes_like_a_file() {
+        let content = "item1,item2,item3\nmilk,bread\nmilk,bread,butter\nbread,butter\n";
+        let config = AprioriConfig::new().min_support_count(2).min_confidence(0.5);
+
+        let from_bytes = config.clone().run_on_reader(content.as_bytes()).unwrap();
+        assert_eq!(sorted_counts(&from_bytes.fre_sets), basket_counts());
+
+        let from_file = config.clone().run_on_file(&temp_file("baskets_reader.csv", content)).unwrap();
+        assert_eq!(counts(&from_bytes.fre_sets), counts(&from_file.fre_sets));
+        assert_eq!(from_bytes.association_rules_set, from_file.association_rules_set);
+    }
+
+
+    #[test]
+    fn normalize_items_merges_the_spellings_of_an_item() {
+        let content = "item1,item2,item3\nMilk,bread\nmilk,Bread,BUTTER\nbread ,butter\n";
+        let config = AprioriConfig::new().min_support_count(2);
+
+        let normalized = config.clone().normalize_items(str::to_lowercase).run_on_reader(content.as_bytes()).unwrap();
+        assert_eq!(sorted_counts(&normalized.fre_sets), basket_counts());
+
+        // without it Milk and milk are two items of count 1
+        let raw = config.clone().run_on_reader(content.as_bytes()).unwrap();
+        assert_eq!(sorted_counts(&raw.fre_sets), expected_counts(&[(&["bread"], 2)]));
+    }
+
+
+    #[test]
+    fn apriori_with_stats_counts_the_textbook_candidates_and_sets() {
+        let rows: Vec<String> = textbook().iter().map(|x| x.items.join(",")).collect();
+        let filename = temp_file("textbook.csv", &format!("item1,item2,item3,item4\n{}\n", rows.join("\n")));
+
+        // 0.25 of 9 txns is a min_count of 2, as in the textbook
+        let (fre_sets, rules, stats) = apriori_with_stats(0.25, 0.5, &filename).unwrap();
+        let result = AprioriConfig::new().min_support(0.25).min_confidence(0.5).run_on_file(&filename).unwrap();
+
+        // 5 items, the 10 pairs of them, the 2 triples left once those with an infrequent pair are pruned,
+        // and no quadruple, {I1, I2, I3, I5} holds the infrequent {I1, I3, I5}
+        assert_eq!(result.candidate_counts, vec![(1, 5), (2, 10), (3, 2), (4, 0)]);
+        let per_degree: Vec<usize> = (1..=3).map(|degree| fre_sets.iter().filter(|x| x.degree == degree).count()).collect();
+        assert_eq!(per_degree, vec![5, 6, 2]);
+
+        assert_eq!(counts(&fre_sets), counts(&textbook_fre_sets()));
+        assert_eq!(rules, textbook_rules());
+        assert_eq!((stats.num_frequent_sets, stats.num_rules, stats.num_candidates_evaluated), (13, 16, 17));
+        assert_eq!(stats, AprioriStats { frequent_set_duration: stats.frequent_set_duration, rule_duration: stats.rule_duration, ..result.stats() });
+    }
+}
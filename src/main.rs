@@ -1,6 +1,46 @@
-use apriori::{apriori, write_rules_to_file, get_good_filename};
+use apriori::{write_rules_to_file, get_good_filename, AprioriConfig, AprioriError, ProgressEvent};
 
-fn main() {
+/// print the progress of the run as it goes
+fn print_progress(event: ProgressEvent) {
+    match event {
+        ProgressEvent::FrequentSetsStarted { min_count, total_weight } => {
+            println!("\nStarting to find all FrequentSet **********************************************");
+            println!("min_count for support: {}, total weight: {}\n", min_count, total_weight);
+        },
+        ProgressEvent::DegreeStarted { degree, frequent_below, .. } => {
+            println!("degree: {}, num of corresponding frequentSet: {}", degree - 1, frequent_below);
+        },
+        ProgressEvent::DegreeFinished { .. } => {},
+        ProgressEvent::FrequentSetsFinished { duration, .. } => {
+            println!("\nFinished to find all FrequentSet **********************************************");
+            println!("It takes {:#?} to find all frequentSets", duration);
+        },
+        ProgressEvent::RulesStarted { min_conf } => {
+            println!("\nStarting to find all Association Rules **********************************************");
+            println!("min_conf: {}", min_conf);
+        },
+        ProgressEvent::RuleDegreeSoftLimit { degree } => {
+            println!("degree {} is above the soft limit, only single-item consequents", degree);
+        },
+        ProgressEvent::ZeroAntecedentSupport { degree } => {
+            println!("a split of a frequentSet of degree {} has an antecedent without support, skipped", degree);
+        },
+        ProgressEvent::MissingSubset { degree } => {
+            println!("a split of a frequentSet of degree {} has a side missing from the frequentSets, skipped", degree);
+        },
+        ProgressEvent::RulesFinished { duration, .. } => {
+            println!("Finished to find all Association Rules **********************************************");
+            println!("It takes {:#?} to find all association rules", duration);
+        },
+        ProgressEvent::SpaceConsumption { fre_sets_bytes, fre_sets, rules_bytes, rules } => {
+            println!("\nSpace Consumption");
+            println!("The size, len of fre_sets: {} bytes, {}", fre_sets_bytes, fre_sets);
+            println!("The size, len of association_rules_set: {} bytes, {}", rules_bytes, rules);
+        },
+    }
+}
+
+fn main() -> Result<(), AprioriError> {
 
     // set min_sup and min_conf
     let min_sup = 0.05;
@@ -9,9 +49,15 @@ fn main() {
     // get good filename based on min_sup and min_conf
     let filename = get_good_filename(min_sup, min_conf);
 
-    // call the apriori function
-    let (_fre_sets, association_rules_set) = apriori(min_sup, min_conf, "groceries.csv");
+    // run apriori, printing its progress
+    let result = AprioriConfig::new()
+        .min_support(min_sup)
+        .min_confidence(min_conf)
+        .progress(print_progress)
+        .run_on_file("groceries.csv")?;
 
     // write all association rules to file
-    write_rules_to_file(&filename, &association_rules_set);
+    write_rules_to_file(&filename, &result.association_rules_set)?;
+
+    Ok(())
 }